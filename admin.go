@@ -0,0 +1,81 @@
+package main
+
+import (
+	"bytes"
+	"crypto/subtle"
+	"encoding/json"
+	"net/http"
+	"strings"
+
+	"github.com/allegro/bigcache/v3"
+	"github.com/vicanso/elton"
+	"github.com/vicanso/hes"
+)
+
+var errInvalidAdminToken = hes.NewWithStatusCode("invalid admin token", http.StatusUnauthorized)
+
+// 管理接口校验，需设置Authorization: Bearer <token>
+func newAdminAuthMiddleware(token string) elton.Handler {
+	return func(c *elton.Context) error {
+		value := c.GetRequestHeader("Authorization")
+		if !strings.HasPrefix(value, "Bearer ") {
+			return errInvalidAdminToken
+		}
+		value = strings.TrimPrefix(value, "Bearer ")
+		if subtle.ConstantTimeCompare([]byte(value), []byte(token)) != 1 {
+			return errInvalidAdminToken
+		}
+		return c.Next()
+	}
+}
+
+// 清除缓存，指定path时只清除该路径的缓存
+func newPurgeHandler(cache *bigcache.BigCache) elton.Handler {
+	return func(c *elton.Context) error {
+		count := 0
+		path := c.QueryParam("path")
+		if path != "" {
+			for _, key := range []string{http.MethodGet + " " + path, http.MethodHead + " " + path, headMetadataPrefix + path} {
+				if cache.Delete(key) == nil {
+					count++
+				}
+			}
+		} else {
+			count = cache.Len()
+			err := cache.Reset()
+			if err != nil {
+				return err
+			}
+		}
+		buf, err := json.Marshal(map[string]int{
+			"count": count,
+		})
+		if err != nil {
+			return err
+		}
+		c.SetContentTypeByExt(".json")
+		c.BodyBuffer = bytes.NewBuffer(buf)
+		return nil
+	}
+}
+
+// 缓存统计，内存占用为bigcache已分配的容量
+func newStatsHandler(cache *bigcache.BigCache) elton.Handler {
+	return func(c *elton.Context) error {
+		stats := cache.Stats()
+		buf, err := json.Marshal(map[string]int64{
+			"count":      int64(cache.Len()),
+			"capacity":   int64(cache.Capacity()),
+			"hits":       stats.Hits,
+			"misses":     stats.Misses,
+			"collisions": stats.Collisions,
+		})
+		if err != nil {
+			return err
+		}
+		c.NoCache()
+		c.SetContentTypeByExt(".json")
+		c.BodyBuffer = bytes.NewBuffer(buf)
+		return nil
+	}
+}
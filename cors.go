@@ -0,0 +1,61 @@
+package main
+
+import (
+	"net/http"
+
+	"github.com/vicanso/elton"
+)
+
+type corsConfig struct {
+	allowOrigins []string
+	allowMethods string
+	allowHeaders string
+}
+
+// 跨域设置，预检请求直接返回204
+func newCORSMiddleware(conf corsConfig) elton.Handler {
+	if conf.allowMethods == "" {
+		conf.allowMethods = "GET, HEAD, OPTIONS"
+	}
+	return func(c *elton.Context) error {
+		origin := c.GetRequestHeader("Origin")
+		if origin == "" {
+			return c.Next()
+		}
+		allowOrigin := ""
+		for _, item := range conf.allowOrigins {
+			if item == "*" || item == origin {
+				allowOrigin = item
+				break
+			}
+		}
+		if allowOrigin == "" {
+			return c.Next()
+		}
+		setAllowOrigin := func() {
+			c.SetHeader("Access-Control-Allow-Origin", allowOrigin)
+			if allowOrigin != "*" {
+				c.Header().Add("Vary", "Origin")
+			}
+		}
+		// 非预检请求在处理完成后才设置，避免被缓存中间件保存到缓存的响应头中
+		if c.Request.Method != http.MethodOptions ||
+			c.GetRequestHeader("Access-Control-Request-Method") == "" {
+			err := c.Next()
+			setAllowOrigin()
+			return err
+		}
+		setAllowOrigin()
+		c.SetHeader("Access-Control-Allow-Methods", conf.allowMethods)
+		allowHeaders := conf.allowHeaders
+		// 未指定则允许请求的所有头
+		if allowHeaders == "" {
+			allowHeaders = c.GetRequestHeader("Access-Control-Request-Headers")
+		}
+		if allowHeaders != "" {
+			c.SetHeader("Access-Control-Allow-Headers", allowHeaders)
+		}
+		c.StatusCode = http.StatusNoContent
+		return nil
+	}
+}
@@ -5,6 +5,7 @@ go 1.18
 require (
 	github.com/allegro/bigcache/v3 v3.0.2
 	github.com/vicanso/elton v1.9.3
+	github.com/vicanso/hes v0.6.0
 )
 
 require (
@@ -12,7 +13,6 @@ require (
 	github.com/tidwall/gjson v1.14.1 // indirect
 	github.com/tidwall/match v1.1.1 // indirect
 	github.com/tidwall/pretty v1.2.0 // indirect
-	github.com/vicanso/hes v0.6.0 // indirect
 	github.com/vicanso/intranet-ip v0.1.0 // indirect
 	github.com/vicanso/keygrip v1.2.1 // indirect
 )
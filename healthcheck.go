@@ -0,0 +1,69 @@
+package main
+
+import (
+	"context"
+	"crypto/tls"
+	"fmt"
+	"net"
+	"net/http"
+	"strings"
+	"time"
+)
+
+// 根据监听地址生成健康检查的地址，未指定或监听所有地址时使用本机地址
+func healthcheckURL(addr string, tlsEnabled bool) (string, error) {
+	scheme := "http"
+	if tlsEnabled {
+		scheme = "https"
+	}
+	// unix socket由连接指定，地址中的host无意义
+	if strings.HasPrefix(addr, "unix:") {
+		return scheme + "://unix/ping", nil
+	}
+	host, port, err := net.SplitHostPort(addr)
+	if err != nil {
+		return "", err
+	}
+	ip := net.ParseIP(host)
+	if host == "" || (ip != nil && ip.IsUnspecified()) {
+		host = "127.0.0.1"
+		if ip != nil && ip.To4() == nil {
+			host = "::1"
+		}
+	}
+	return scheme + "://" + net.JoinHostPort(host, port) + "/ping", nil
+}
+
+// 请求监听的第一个地址的/ping，用于容器的健康检查
+func healthcheck(addr string, tlsEnabled bool) error {
+	url, err := healthcheckURL(addr, tlsEnabled)
+	if err != nil {
+		return err
+	}
+	transport := &http.Transport{
+		// 证书对应的是对外的域名，本机检查不校验
+		TLSClientConfig: &tls.Config{
+			InsecureSkipVerify: true,
+		},
+	}
+	if strings.HasPrefix(addr, "unix:") {
+		file := strings.TrimPrefix(addr, "unix:")
+		transport.DialContext = func(ctx context.Context, _, _ string) (net.Conn, error) {
+			var d net.Dialer
+			return d.DialContext(ctx, "unix", file)
+		}
+	}
+	client := &http.Client{
+		Transport: transport,
+		Timeout:   5 * time.Second,
+	}
+	resp, err := client.Get(url)
+	if err != nil {
+		return err
+	}
+	defer resp.Body.Close()
+	if resp.StatusCode != http.StatusOK {
+		return fmt.Errorf("healthcheck fail, status: %d", resp.StatusCode)
+	}
+	return nil
+}
@@ -0,0 +1,33 @@
+package main
+
+import (
+	"testing"
+)
+
+func TestHealthcheckURL(t *testing.T) {
+	tests := []struct {
+		addr string
+		tls  bool
+		url  string
+	}{
+		{":3000", false, "http://127.0.0.1:3000/ping"},
+		{":3000", true, "https://127.0.0.1:3000/ping"},
+		{"0.0.0.0:8080", false, "http://127.0.0.1:8080/ping"},
+		{"[::]:8080", false, "http://[::1]:8080/ping"},
+		{"192.168.1.10:3000", true, "https://192.168.1.10:3000/ping"},
+		{"unix:/tmp/static.sock", false, "http://unix/ping"},
+	}
+	for _, tt := range tests {
+		url, err := healthcheckURL(tt.addr, tt.tls)
+		if err != nil {
+			t.Fatal(err)
+		}
+		if url != tt.url {
+			t.Errorf("%s, expected %s, got %s", tt.addr, tt.url, url)
+		}
+	}
+	_, err := healthcheckURL("3000", false)
+	if err == nil {
+		t.Errorf("address without port should be invalid")
+	}
+}
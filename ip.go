@@ -0,0 +1,127 @@
+package main
+
+import (
+	"context"
+	"net"
+	"net/http"
+	"strings"
+
+	"github.com/vicanso/elton"
+	"github.com/vicanso/hes"
+)
+
+// 受信的代理地址，只有来自受信代理的请求才使用X-Forwarded-For与X-Real-Ip
+var trustedProxies []*net.IPNet
+
+// 解析以,分隔的CIDR，单个IP视为/32或/128
+func parseCIDRs(value string) ([]*net.IPNet, error) {
+	result := make([]*net.IPNet, 0)
+	for _, item := range splitAndTrim(value) {
+		if !strings.Contains(item, "/") {
+			if strings.Contains(item, ":") {
+				item += "/128"
+			} else {
+				item += "/32"
+			}
+		}
+		_, ipNet, err := net.ParseCIDR(item)
+		if err != nil {
+			return nil, err
+		}
+		result = append(result, ipNet)
+	}
+	return result, nil
+}
+
+// 以,分隔输出网段，用于输出配置
+func formatCIDRs(ipNets []*net.IPNet) string {
+	arr := make([]string, len(ipNets))
+	for i, ipNet := range ipNets {
+		arr[i] = ipNet.String()
+	}
+	return strings.Join(arr, ",")
+}
+
+func containsIP(ipNets []*net.IPNet, ip net.IP) bool {
+	for _, ipNet := range ipNets {
+		if ipNet.Contains(ip) {
+			return true
+		}
+	}
+	return false
+}
+
+type connNetworkKey struct{}
+
+// 记录连接的网络类型，用于识别unix socket的连接
+func withConnNetwork(ctx context.Context, conn net.Conn) context.Context {
+	return context.WithValue(ctx, connNetworkKey{}, conn.LocalAddr().Network())
+}
+
+func isUnixConn(req *http.Request) bool {
+	network, _ := req.Context().Value(connNetworkKey{}).(string)
+	return network == "unix"
+}
+
+// 获取客户端IP
+func getClientIP(c *elton.Context) string {
+	return resolveClientIP(
+		trustedProxies,
+		c.Request.RemoteAddr,
+		c.GetRequestHeader("X-Forwarded-For"),
+		c.GetRequestHeader("X-Real-Ip"),
+		isUnixConn(c.Request),
+	)
+}
+
+// 根据连接地址与转发请求头获取客户端IP，X-Forwarded-For中取最右侧非受信代理的地址，
+// unix socket的连接只能来自本机的代理，视为受信代理
+func resolveClientIP(proxies []*net.IPNet, remoteAddr, xff, realIP string, unixConn bool) string {
+	remote, _, err := net.SplitHostPort(remoteAddr)
+	if err != nil {
+		remote = remoteAddr
+	}
+	if !unixConn {
+		ip := net.ParseIP(remote)
+		if ip == nil || !containsIP(proxies, ip) {
+			return remote
+		}
+	}
+	if xff != "" {
+		arr := strings.Split(xff, ",")
+		for i := len(arr) - 1; i >= 0; i-- {
+			item := strings.TrimSpace(arr[i])
+			v := net.ParseIP(item)
+			if v == nil {
+				return remote
+			}
+			if !containsIP(proxies, v) {
+				return item
+			}
+		}
+		// 均为受信代理时使用最左侧的地址
+		return strings.TrimSpace(arr[0])
+	}
+	if net.ParseIP(realIP) != nil {
+		return realIP
+	}
+	return remote
+}
+
+// IP访问控制，禁止列表优先，设置允许列表时不在列表中的IP也禁止访问
+func newIPFilterMiddleware(allowIPs, denyIPs []*net.IPNet) elton.Handler {
+	errForbidden := hes.NewWithStatusCode("forbidden", http.StatusForbidden)
+	return func(c *elton.Context) error {
+		if c.Request.URL.Path == "/ping" {
+			return c.Next()
+		}
+		ip := net.ParseIP(getClientIP(c))
+		if ip == nil || containsIP(denyIPs, ip) {
+			return errForbidden
+		}
+		if len(allowIPs) != 0 && !containsIP(allowIPs, ip) {
+			return errForbidden
+		}
+		return c.Next()
+	}
+}
@@ -0,0 +1,69 @@
+package main
+
+import (
+	"testing"
+)
+
+func TestParseCIDRs(t *testing.T) {
+	ipNets, err := parseCIDRs("192.168.1.1, 10.0.0.0/8,::1")
+	if err != nil {
+		t.Fatal(err)
+	}
+	expected := []string{"192.168.1.1/32", "10.0.0.0/8", "::1/128"}
+	if len(ipNets) != len(expected) {
+		t.Fatalf("expected %d ip nets, got %d", len(expected), len(ipNets))
+	}
+	for i, ipNet := range ipNets {
+		if ipNet.String() != expected[i] {
+			t.Errorf("expected %s, got %s", expected[i], ipNet.String())
+		}
+	}
+
+	ipNets, err = parseCIDRs("")
+	if err != nil || len(ipNets) != 0 {
+		t.Errorf("empty value should return no ip net, %v", err)
+	}
+
+	_, err = parseCIDRs("10.0.0.0/8,abc")
+	if err == nil {
+		t.Error("invalid cidr should return error")
+	}
+}
+
+func TestResolveClientIP(t *testing.T) {
+	proxies, err := parseCIDRs("10.0.0.0/8")
+	if err != nil {
+		t.Fatal(err)
+	}
+	tests := []struct {
+		remoteAddr string
+		xff        string
+		realIP     string
+		unixConn   bool
+		ip         string
+	}{
+		// 非受信代理不使用转发的请求头
+		{"1.1.1.1:80", "2.2.2.2", "3.3.3.3", false, "1.1.1.1"},
+		{"10.0.0.1:80", "", "", false, "10.0.0.1"},
+		{"10.0.0.1:80", "2.2.2.2, 10.0.0.2", "", false, "2.2.2.2"},
+		// 取最右侧非受信代理的地址
+		{"10.0.0.1:80", "3.3.3.3, 2.2.2.2", "", false, "2.2.2.2"},
+		{"10.0.0.1:80", "10.0.0.3, 10.0.0.2", "", false, "10.0.0.3"},
+		{"10.0.0.1:80", "2.2.2.2, abc", "", false, "10.0.0.1"},
+		{"10.0.0.1:80", "", "2.2.2.2", false, "2.2.2.2"},
+		{"10.0.0.1:80", "", "abc", false, "10.0.0.1"},
+		{"[::1]:80", "2.2.2.2", "", false, "::1"},
+		// unix socket的连接视为受信代理
+		{"@", "2.2.2.2", "", true, "2.2.2.2"},
+		{"", "", "2.2.2.2", true, "2.2.2.2"},
+		{"@", "", "", true, "@"},
+		{"@", "2.2.2.2", "", false, "@"},
+	}
+	for _, tt := range tests {
+		ip := resolveClientIP(proxies, tt.remoteAddr, tt.xff, tt.realIP, tt.unixConn)
+		if ip != tt.ip {
+			t.Errorf("remote: %s, xff: %s, real ip: %s, unix: %v, expected %s, got %s",
+				tt.remoteAddr, tt.xff, tt.realIP, tt.unixConn, tt.ip, ip)
+		}
+	}
+}
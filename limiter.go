@@ -0,0 +1,120 @@
+package main
+
+import (
+	"math"
+	"net/http"
+	"strconv"
+	"sync"
+	"time"
+
+	"github.com/vicanso/elton"
+	"github.com/vicanso/hes"
+)
+
+type tokenBucket struct {
+	tokens    float64
+	updatedAt time.Time
+}
+
+type rateLimiter struct {
+	mutex   sync.Mutex
+	rate    float64
+	burst   float64
+	buckets map[string]*tokenBucket
+}
+
+func newRateLimiter(rate float64, burst int) *rateLimiter {
+	rl := &rateLimiter{
+		rate:    rate,
+		burst:   float64(burst),
+		buckets: make(map[string]*tokenBucket),
+	}
+	go func() {
+		for range time.Tick(time.Minute) {
+			rl.evict()
+		}
+	}()
+	return rl
+}
+
+// 获取令牌，若失败则返回需要等待的时长
+func (rl *rateLimiter) take(key string) (bool, time.Duration) {
+	rl.mutex.Lock()
+	defer rl.mutex.Unlock()
+	now := time.Now()
+	b, ok := rl.buckets[key]
+	if !ok {
+		b = &tokenBucket{
+			tokens:    rl.burst,
+			updatedAt: now,
+		}
+		rl.buckets[key] = b
+	}
+	b.tokens = math.Min(rl.burst, b.tokens+now.Sub(b.updatedAt).Seconds()*rl.rate)
+	b.updatedAt = now
+	if b.tokens < 1 {
+		return false, time.Duration((1 - b.tokens) / rl.rate * float64(time.Second))
+	}
+	b.tokens--
+	return true, 0
+}
+
+// 删除已回满的令牌桶，避免内存持续增长
+func (rl *rateLimiter) evict() {
+	rl.mutex.Lock()
+	defer rl.mutex.Unlock()
+	now := time.Now()
+	for key, b := range rl.buckets {
+		if b.tokens+now.Sub(b.updatedAt).Seconds()*rl.rate >= rl.burst {
+			delete(rl.buckets, key)
+		}
+	}
+}
+
+// 按客户端IP限制请求频率，健康检查不限制
+func newRateLimitMiddleware(rl *rateLimiter) elton.Handler {
+	return func(c *elton.Context) error {
+		if c.Request.URL.Path == "/ping" {
+			return c.Next()
+		}
+		ok, wait := rl.take(getClientIP(c))
+		if !ok {
+			c.SetHeader("Retry-After", strconv.Itoa(int(math.Ceil(wait.Seconds()))))
+			return hes.NewWithStatusCode("too many requests", http.StatusTooManyRequests)
+		}
+		return c.Next()
+	}
+}
+
+// 限制同时处理的请求数(包括响应数据的输出)，超出时返回503，健康检查不限制
+func newConcurrencyLimitHandler(handler http.Handler, max int) http.Handler {
+	sem := make(chan struct{}, max)
+	return http.HandlerFunc(func(w http.ResponseWriter, r *http.Request) {
+		if r.URL.Path == "/ping" {
+			handler.ServeHTTP(w, r)
+			return
+		}
+		select {
+		case sem <- struct{}{}:
+		default:
+			http.Error(w, "too many concurrent requests", http.StatusServiceUnavailable)
+			return
+		}
+		defer func() {
+			<-sem
+		}()
+		handler.ServeHTTP(w, r)
+	})
+}
+
+// 限制请求体大小，静态文件服务不需要请求体
+func newBodyLimitMiddleware(max int64) elton.Handler {
+	errTooLarge := hes.NewWithStatusCode("request body too large", http.StatusRequestEntityTooLarge)
+	return func(c *elton.Context) error {
+		if c.Request.ContentLength > max {
+			return errTooLarge
+		}
+		c.Request.Body = http.MaxBytesReader(c.Response, c.Request.Body, max)
+		return c.Next()
+	}
+}
@@ -0,0 +1,122 @@
+package main
+
+import (
+	"encoding/json"
+	"log"
+	"math"
+	"os"
+	"strconv"
+	"strings"
+	"sync/atomic"
+	"time"
+
+	"github.com/vicanso/elton"
+	"github.com/vicanso/elton/middleware"
+)
+
+type accessLog struct {
+	Level     string `json:"level"`
+	Time      string `json:"time"`
+	IP        string `json:"ip"`
+	Method    string `json:"method"`
+	URI       string `json:"uri"`
+	Status    int    `json:"status"`
+	Size      int    `json:"size"`
+	Duration  int    `json:"duration"`
+	UserAgent string `json:"user_agent"`
+	RequestID string `json:"request_id"`
+	Slow      bool   `json:"slow,omitempty"`
+}
+
+// 日志采样，2xx的请求按比例输出，其它状态码与慢请求均输出
+type logSampler struct {
+	every uint64
+	count uint64
+}
+
+func newLogSampler(rate float64) *logSampler {
+	var every uint64
+	if rate >= 1 {
+		every = 1
+	} else if rate > 0 {
+		every = uint64(math.Round(1 / rate))
+	}
+	return &logSampler{
+		every: every,
+	}
+}
+
+func (ls *logSampler) skip(status int) bool {
+	if status < 200 || status >= 300 || ls.every == 1 {
+		return false
+	}
+	if ls.every == 0 {
+		return true
+	}
+	return atomic.AddUint64(&ls.count, 1)%ls.every != 0
+}
+
+// 访问日志，format为json时每行输出一个json，否则为combined格式
+// 耗时超过slowMs的请求以warn级别输出，不受采样影响
+func newAccessLogger(format string, slowMs int, sampler *logSampler) elton.Handler {
+	if format != "json" {
+		return middleware.NewLogger(middleware.LoggerConfig{
+			OnLog: func(s string, c *elton.Context) {
+				// 前两项为状态码与耗时(ms)
+				arr := strings.SplitN(s, " ", 3)
+				if len(arr) != 3 {
+					return
+				}
+				status, _ := strconv.Atoi(arr[0])
+				duration, _ := strconv.Atoi(arr[1])
+				if duration > slowMs {
+					log.Println("[WARN]", arr[2], c.GetHeader(headerRequestID), "slow=true")
+					return
+				}
+				if sampler.skip(status) {
+					return
+				}
+				log.Println(arr[2], c.GetHeader(headerRequestID))
+			},
+			Format: "{status} {latency-ms} " + middleware.LoggerCombined,
+		})
+	}
+	jsonLogger := log.New(os.Stdout, "", 0)
+	return middleware.NewLogger(middleware.LoggerConfig{
+		OnLog: func(s string, c *elton.Context) {
+			// 状态码、数据长度与耗时(ms)
+			arr := strings.Split(s, " ")
+			if len(arr) != 3 {
+				return
+			}
+			status, _ := strconv.Atoi(arr[0])
+			size, _ := strconv.Atoi(arr[1])
+			duration, _ := strconv.Atoi(arr[2])
+			level := "info"
+			slow := duration > slowMs
+			if slow {
+				level = "warn"
+			} else if sampler.skip(status) {
+				return
+			}
+			buf, err := json.Marshal(&accessLog{
+				Level:     level,
+				Time:      time.Now().Format(time.RFC3339),
+				IP:        getClientIP(c),
+				Method:    c.Request.Method,
+				URI:       c.Request.RequestURI,
+				Status:    status,
+				Size:      size,
+				Duration:  duration,
+				UserAgent: c.Request.UserAgent(),
+				RequestID: c.GetHeader(headerRequestID),
+				Slow:      slow,
+			})
+			if err != nil {
+				return
+			}
+			jsonLogger.Println(string(buf))
+		},
+		Format: "{status} {size} {latency-ms}",
+	})
+}
@@ -3,10 +3,19 @@ package main
 import (
 	"bytes"
 	"context"
+	"crypto/tls"
 	"fmt"
+	"math"
+	"mime"
+	"net/http"
 	"os"
+	"os/signal"
+	"path/filepath"
 	"regexp"
 	"strconv"
+	"strings"
+	"sync/atomic"
+	"syscall"
 	"time"
 
 	"log"
@@ -14,6 +23,7 @@ import (
 	"github.com/allegro/bigcache/v3"
 	"github.com/vicanso/elton"
 	"github.com/vicanso/elton/middleware"
+	"github.com/vicanso/hes"
 )
 
 type httpCache struct {
@@ -33,6 +43,13 @@ func (hc *httpCache) Set(ctx context.Context, key string, data []byte, ttl time.
 }
 
 func main() {
+	// 配置文件中的值作为环境变量的默认值，已设置的环境变量优先
+	if file := os.Getenv("STATIC_CONFIG_FILE"); file != "" {
+		err := loadConfigFile(file)
+		if err != nil {
+			log.Fatal("STATIC_CONFIG_FILE is invalid, " + err.Error())
+		}
+	}
 	staticPath := os.Getenv("STATIC")
 	compressLevel, _ := strconv.Atoi(os.Getenv("CMP_LEVEL"))
 	minLength, _ := strconv.Atoi(os.Getenv("CMP_MIN_LENGTH"))
@@ -45,15 +62,183 @@ func main() {
 	if cacheTTL == 0 {
 		cacheTTL = 10 * time.Minute
 	}
+	// 默认延时5秒再关闭，便于负载均衡摘除节点
+	shutdownDelay := 5 * time.Second
+	if value := os.Getenv("STATIC_SHUTDOWN_DELAY"); value != "" {
+		d, err := time.ParseDuration(value)
+		if err != nil {
+			log.Fatal("STATIC_SHUTDOWN_DELAY is invalid, " + err.Error())
+		}
+		shutdownDelay = d
+	}
+	tlsCert := os.Getenv("STATIC_TLS_CERT")
+	tlsKey := os.Getenv("STATIC_TLS_KEY")
+	// 证书与私钥需同时配置
+	if (tlsCert == "") != (tlsKey == "") {
+		log.Fatal("STATIC_TLS_CERT and STATIC_TLS_KEY should be set together")
+	}
+	listenAddrs := splitAndTrim(os.Getenv("STATIC_LISTEN_ADDR"))
+	if len(listenAddrs) == 0 {
+		listenAddrs = []string{":3000"}
+	}
+	// 容器的健康检查，请求监听的第一个地址
+	if len(os.Args) > 1 && os.Args[1] == "--healthcheck" {
+		err := healthcheck(listenAddrs[0], tlsCert != "")
+		if err != nil {
+			log.Fatal(err.Error())
+		}
+		return
+	}
+	indexFiles := splitAndTrim(os.Getenv("STATIC_INDEX_FILE"))
+	if len(indexFiles) == 0 {
+		indexFiles = []string{"index.html"}
+	}
+	// 启动时检查静态目录与首页是否可访问
+	if checkStorageOnStart, _ := strconv.ParseBool(os.Getenv("STATIC_CHECK_STORAGE_ON_START")); checkStorageOnStart {
+		err := checkStorage(staticPath, indexFiles)
+		if err != nil {
+			log.Fatal(err.Error())
+		}
+	}
+	proxies, err := parseCIDRs(os.Getenv("STATIC_TRUSTED_PROXIES"))
+	if err != nil {
+		log.Fatal("STATIC_TRUSTED_PROXIES is invalid, " + err.Error())
+	}
+	trustedProxies = proxies
+	// 覆盖默认的文件类型，响应的Content-Type均由扩展名获取
+	for ext, mimeType := range parseExtValues(os.Getenv("STATIC_MIME_OVERRIDES"), ",") {
+		err := mime.AddExtensionType("."+ext, mimeType)
+		if err != nil {
+			log.Fatal("STATIC_MIME_OVERRIDES is invalid, " + err.Error())
+		}
+	}
 	e := elton.New()
 
-	e.Use(middleware.NewLogger(middleware.LoggerConfig{
-		OnLog: func(s string, _ *elton.Context) {
-			log.Println(s)
-		},
-		Format: middleware.LoggerCombined,
-	}))
+	slowRequestMs, _ := strconv.Atoi(os.Getenv("STATIC_SLOW_REQUEST_MS"))
+	if slowRequestMs <= 0 {
+		slowRequestMs = 1000
+	}
+	logSampleRate := 1.0
+	if value := os.Getenv("STATIC_LOG_SAMPLE_RATE"); value != "" {
+		logSampleRate, err = strconv.ParseFloat(value, 64)
+		if err != nil || logSampleRate < 0 || logSampleRate > 1 {
+			log.Fatal("STATIC_LOG_SAMPLE_RATE should be between 0 and 1")
+		}
+	}
+	e.Use(newAccessLogger(os.Getenv("LOG_FORMAT"), slowRequestMs, newLogSampler(logSampleRate)))
+	metricsEnabled, _ := strconv.ParseBool(os.Getenv("STATIC_METRICS"))
+	metrics := newHTTPMetrics()
+	e.Use(newRequestIDMiddleware())
+	cacheMode := os.Getenv("STATIC_CACHE_MODE")
+	serverTimingEnabled, _ := strconv.ParseBool(os.Getenv("STATIC_SERVER_TIMING"))
+	if serverTimingEnabled {
+		e.Use(newServerTimingMiddleware())
+	}
+	cacheStatusHeader, _ := strconv.ParseBool(os.Getenv("STATIC_CACHE_STATUS_HEADER"))
+	if cacheStatusHeader {
+		e.Use(newCacheStatusHeaderMiddleware())
+	}
+	allowIPs, err := parseCIDRs(os.Getenv("STATIC_ALLOW_IPS"))
+	if err != nil {
+		log.Fatal("STATIC_ALLOW_IPS is invalid, " + err.Error())
+	}
+	denyIPs, err := parseCIDRs(os.Getenv("STATIC_DENY_IPS"))
+	if err != nil {
+		log.Fatal("STATIC_DENY_IPS is invalid, " + err.Error())
+	}
+	if len(allowIPs) != 0 || len(denyIPs) != 0 {
+		e.Use(newIPFilterMiddleware(allowIPs, denyIPs))
+	}
+	maxRequestBody, err := strconv.ParseInt(os.Getenv("STATIC_MAX_REQUEST_BODY"), 10, 64)
+	if err != nil || maxRequestBody <= 0 {
+		maxRequestBody = 64 * 1024
+	}
+	e.Use(newBodyLimitMiddleware(maxRequestBody))
+	rewriteRules, err := parseRewriteRules(os.Getenv("STATIC_REWRITE_RULES"))
+	if err != nil {
+		log.Fatal("STATIC_REWRITE_RULES is invalid, " + err.Error())
+	}
+	// 需在路径校验之前，重写后的路径同样需要校验
+	if len(rewriteRules) != 0 {
+		e.Use(newRewriteMiddleware(rewriteRules))
+	}
+	e.Use(newPathCheckMiddleware())
+	if securityHeaders, _ := strconv.ParseBool(os.Getenv("STATIC_SECURITY_HEADERS")); securityHeaders {
+		e.Use(newSecurityHeadersMiddleware(os.Getenv("STATIC_CSP")))
+	}
+	// 需在安全响应头之后，安全响应头不覆盖已设置的值
+	pathHeaders := parsePathHeaders(os.Environ())
+	if len(pathHeaders) != 0 {
+		e.Use(newPathHeaderMiddleware(pathHeaders))
+	}
+	// 需在基础认证之前，认证失败的请求同样限频
+	rateLimit, _ := strconv.ParseFloat(os.Getenv("STATIC_RATE_LIMIT"), 64)
+	rateBurst := 0
+	if rateLimit > 0 {
+		rateBurst, _ = strconv.Atoi(os.Getenv("STATIC_RATE_BURST"))
+		if rateBurst <= 0 {
+			rateBurst = int(math.Ceil(rateLimit))
+		}
+		e.Use(newRateLimitMiddleware(newRateLimiter(rateLimit, rateBurst)))
+	}
+	cors := corsConfig{
+		allowOrigins: splitAndTrim(os.Getenv("STATIC_CORS_ALLOW_ORIGIN")),
+		allowMethods: os.Getenv("STATIC_CORS_ALLOW_METHODS"),
+		allowHeaders: os.Getenv("STATIC_CORS_ALLOW_HEADERS"),
+	}
+	corsEnabled := len(cors.allowOrigins) != 0 || cors.allowMethods != "" || cors.allowHeaders != ""
+	if corsEnabled {
+		if len(cors.allowOrigins) == 0 {
+			cors.allowOrigins = []string{"*"}
+		}
+		e.Use(newCORSMiddleware(cors))
+	}
+	basicAuth := os.Getenv("STATIC_BASIC_AUTH")
+	if basicAuth != "" {
+		arr := strings.SplitN(basicAuth, ":", 2)
+		if len(arr) != 2 {
+			log.Fatal("STATIC_BASIC_AUTH should be user:password")
+		}
+		realm := os.Getenv("STATIC_BASIC_AUTH_REALM")
+		if realm == "" {
+			realm = "static"
+		}
+		e.Use(newBasicAuthMiddleware(arr[0], arr[1], realm))
+	}
+	// 缓存直接使用10分钟
+	// 静态文件有版本号，10分钟短缓存不影响
+	cache, _ := bigcache.NewBigCache(bigcache.DefaultConfig(cacheTTL))
 	e.Use(middleware.NewDefaultFresh())
+	// 需在缓存中间件之前，避免HEAD请求影响缓存的数据
+	e.Use(newHeadMiddleware(staticPath, cache))
+	// 需在缓存中间件之前，缓存中保存的是完整的响应
+	e.Use(newRangeMiddleware())
+	cacheControls := parseExtValues(os.Getenv("STATIC_CACHE_CONTROL_BY_EXT"), ";")
+	if len(cacheControls) != 0 {
+		e.Use(newCacheControlMiddleware(cacheControls))
+	}
+	notFoundPage := os.Getenv("STATIC_NOT_FOUND_PAGE")
+	if notFoundPage != "" {
+		e.Use(newNotFoundPageMiddleware(staticPath + "/" + notFoundPage))
+	}
+	e.Use(newAttachmentMiddleware(parseExts(os.Getenv("STATIC_ATTACHMENT_EXTENSIONS"))))
+	if caseInsensitive, _ := strconv.ParseBool(os.Getenv("STATIC_CASE_INSENSITIVE")); caseInsensitive {
+		e.Use(newCaseInsensitiveMiddleware(staticPath))
+	}
+	followSymlinks, _ := strconv.ParseBool(os.Getenv("STATIC_FOLLOW_SYMLINKS"))
+	e.Use(newSymlinkMiddleware(staticPath, indexFiles, followSymlinks))
+	allowExts := parseExts(os.Getenv("STATIC_ALLOW_EXTENSIONS"))
+	denyExts := parseExts(os.Getenv("STATIC_DENY_EXTENSIONS"))
+	if len(allowExts) != 0 || len(denyExts) != 0 {
+		e.Use(newExtFilterMiddleware(allowExts, denyExts))
+	}
+	// 预压缩文件与静态文件使用相同的缓存时长
+	staticMaxAge := 365 * 24 * time.Hour
+	staticSMaxAge := time.Hour
+	if precompressed, _ := strconv.ParseBool(os.Getenv("STATIC_PRECOMPRESSED")); precompressed {
+		cacheControl := fmt.Sprintf("public, max-age=%d, s-maxage=%d", int(staticMaxAge.Seconds()), int(staticSMaxAge.Seconds()))
+		e.Use(newPrecompressedMiddleware(staticPath, cacheControl))
+	}
 
 	var compressor middleware.CacheCompressor
 	if compressLevel != 0 {
@@ -63,49 +248,245 @@ func main() {
 			ContentRegexp: checker,
 		}
 	}
-	// 缓存直接使用10分钟
-	// 静态文件有版本号，10分钟短缓存不影响
-	cache, _ := bigcache.NewBigCache(bigcache.DefaultConfig(cacheTTL))
 
-	e.Use(middleware.NewCache(middleware.CacheConfig{
-		Store: &httpCache{
-			c: cache,
-		},
-		Compressor: compressor,
-	}))
+	// 记录缓存是否命中，缓存关闭时不记录
+	cacheStatusEnabled := (serverTimingEnabled || cacheStatusHeader) && cacheMode != "off"
+	if cacheStatusEnabled {
+		e.Use(newCacheStatusMiddleware())
+	}
+	// metadata只缓存响应头，off不缓存，304由fresh中间件根据ETag处理
+	switch cacheMode {
+	case "", "full":
+		e.Use(middleware.NewCache(middleware.CacheConfig{
+			Store: &httpCache{
+				c: cache,
+			},
+			Compressor: compressor,
+		}))
+	case "metadata":
+		e.Use(newMetadataCacheMiddleware(cache))
+	case "off":
+	default:
+		log.Fatal("STATIC_CACHE_MODE should be full, metadata or off")
+	}
+	// 需在缓存中间件之后，缓存命中时不会执行
+	if cacheStatusEnabled {
+		e.Use(newCacheMissMiddleware())
+	}
+	// 压缩在缓存中间件中处理，不缓存响应数据时不压缩
+	if compressor != nil && cacheMode != "" && cacheMode != "full" {
+		log.Println("[WARN] compression is disabled because STATIC_CACHE_MODE is " + cacheMode)
+	}
+
+	adminToken := os.Getenv("STATIC_ADMIN_TOKEN")
+	if adminToken != "" {
+		adminAuth := newAdminAuthMiddleware(adminToken)
+		e.POST("/-/purge", adminAuth, newPurgeHandler(cache))
+		e.GET("/-/stats", adminAuth, newStatsHandler(cache))
+		addInternalRoute(http.MethodPost, "/-/purge")
+		addInternalRoute(http.MethodGet, "/-/stats")
+	}
 
 	sf := new(middleware.FS)
-	e.GET("/ping", func(c *elton.Context) error {
+	var closing int32
+	ping := func(c *elton.Context) error {
+		if atomic.LoadInt32(&closing) == 1 {
+			return hes.NewWithStatusCode("server is closing", http.StatusInternalServerError)
+		}
 		c.BodyBuffer = bytes.NewBufferString("pong")
 		return nil
-	})
-	e.GET("/", func(c *elton.Context) (err error) {
-		r, err := sf.NewReader(staticPath + "/index.html")
+	}
+	e.GET("/ping", ping)
+	e.HEAD("/ping", ping)
+	e.GET("/version", versionHandler)
+	addInternalRoute(http.MethodGet, "/ping")
+	addInternalRoute(http.MethodHead, "/ping")
+	addInternalRoute(http.MethodGet, "/version")
+	if metricsEnabled {
+		e.GET("/metrics", newMetricsHandler(metrics))
+		addInternalRoute(http.MethodGet, "/metrics")
+	}
+	index := func(c *elton.Context) (err error) {
+		file, info := findIndexFile(staticPath, indexFiles)
+		if file == "" {
+			return errFileNotFound
+		}
+		r, err := sf.NewReader(file)
 		if err != nil {
 			return
 		}
-		c.SetContentTypeByExt(".html")
+		// 静态文件路由由StaticServe设置，首页需自行设置
+		c.SetHeader("Last-Modified", info.ModTime().UTC().Format(http.TimeFormat))
+		c.SetContentTypeByExt(filepath.Ext(file))
+		if c.Request.Method == http.MethodHead {
+			c.SetHeader("Content-Length", strconv.FormatInt(info.Size(), 10))
+		}
 		c.Body = r
 		return
-	})
+	}
+	e.GET("/", index)
+	e.HEAD("/", index)
+	weakETag, _ := strconv.ParseBool(os.Getenv("STATIC_WEAK_ETAG"))
+	// 强ETag需读取整个文件计算，超过该大小的文件使用弱ETag并以流的方式返回
+	inlineMaxSize, err := strconv.ParseInt(os.Getenv("STATIC_INLINE_MAX_SIZE"), 10, 64)
+	if err != nil || inlineMaxSize <= 0 {
+		inlineMaxSize = 1024 * 1024
+	}
 	// static file route
-	e.GET("/*", middleware.NewStaticServe(sf, middleware.StaticServeConfig{
+	staticServeConfig := middleware.StaticServeConfig{
 		Path: staticPath,
 		// 客户端缓存一年
-		MaxAge: 365 * 24 * time.Hour,
+		MaxAge: staticMaxAge,
 		// 缓存服务器缓存一个小时
-		SMaxAge: time.Hour,
+		SMaxAge: staticSMaxAge,
 		// 禁止访问隐藏文件
 		DenyDot: true,
-		// 启用强ETag
-		EnableStrongETag: true,
-	}))
-	msg := fmt.Sprintf("path:%s, compress(level:%d, minLength:%d, contentType:%s)", staticPath, compressLevel, minLength, contentType)
+	}
+	weakStaticServe := middleware.NewStaticServe(sf, staticServeConfig)
+	// 默认启用强ETag
+	staticServeConfig.EnableStrongETag = true
+	strongStaticServe := middleware.NewStaticServe(sf, staticServeConfig)
+	staticServe := func(c *elton.Context) error {
+		if weakETag {
+			return weakStaticServe(c)
+		}
+		info, err := os.Stat(filepath.Join(staticPath, c.Request.URL.Path))
+		if err == nil && info.Size() > inlineMaxSize {
+			return weakStaticServe(c)
+		}
+		return strongStaticServe(c)
+	}
+	e.GET("/*", staticServe)
+	e.HEAD("/*", staticServe)
+	// 跨域的预检请求由跨域中间件处理
+	e.OPTIONS("/*", func(c *elton.Context) error {
+		c.SetHeader("Allow", allowMethods)
+		c.StatusCode = http.StatusNoContent
+		return nil
+	})
+	methodNotAllowed := newMethodNotAllowedHandler()
+	e.POST("/*", methodNotAllowed)
+	e.PUT("/*", methodNotAllowed)
+	e.PATCH("/*", methodNotAllowed)
+	e.DELETE("/*", methodNotAllowed)
+	// 其它请求方法转由DELETE的路由返回405
+	var handler http.Handler = newMethodRouteHandler(e)
+	// 在elton之外限制，响应数据输出完成后才释放
+	maxConcurrency, _ := strconv.Atoi(os.Getenv("STATIC_MAX_CONCURRENCY"))
+	if maxConcurrency > 0 {
+		handler = newConcurrencyLimitHandler(handler, maxConcurrency)
+	}
+	// 统计实际输出的数据长度与包括输出的耗时，超出并发限制的请求同样统计
+	if metricsEnabled {
+		handler = newMetricsCollector(handler, metrics)
+	}
+	e.Server.Handler = handler
+	// 用于识别unix socket的连接
+	e.Server.ConnContext = withConnNetwork
+	// 读取请求头超时默认为10秒，避免慢速请求长期占用连接
+	e.Server.ReadHeaderTimeout = 10 * time.Second
+	if value := os.Getenv("STATIC_HEADER_TIMEOUT"); value != "" {
+		e.Server.ReadHeaderTimeout, err = time.ParseDuration(value)
+		if err != nil {
+			log.Fatal("STATIC_HEADER_TIMEOUT is invalid, " + err.Error())
+		}
+	}
+	if value := os.Getenv("STATIC_KEEPALIVE"); value != "" {
+		e.Server.IdleTimeout, err = time.ParseDuration(value)
+		if err != nil {
+			log.Fatal("STATIC_KEEPALIVE is invalid, " + err.Error())
+		}
+	}
+	msg := fmt.Sprintf("path:%s, listen:%s, compress(level:%d, minLength:%d, contentType:%s), shutdownDelay:%s", staticPath, strings.Join(listenAddrs, ","), compressLevel, minLength, contentType, shutdownDelay)
 	log.Println(msg)
+	// 仅校验配置，不启动服务
+	check, _ := strconv.ParseBool(os.Getenv("STATIC_CHECK"))
+	if check || (len(os.Args) > 1 && os.Args[1] == "--check") {
+		// 输出解析后的配置
+		rules := make([]string, len(rewriteRules))
+		for i, rule := range rewriteRules {
+			rules[i] = rule.String()
+		}
+		headers := make([]string, len(pathHeaders))
+		for i, header := range pathHeaders {
+			headers[i] = header.prefix + ":" + header.name + "=" + header.value
+		}
+		cacheModeName := cacheMode
+		if cacheModeName == "" {
+			cacheModeName = "full"
+		}
+		summary := []string{
+			"index: " + strings.Join(indexFiles, ","),
+			"tls: " + strconv.FormatBool(tlsCert != ""),
+			fmt.Sprintf("cache(mode:%s, ttl:%s)", cacheModeName, cacheTTL),
+			fmt.Sprintf("cors(enabled:%v, origins:%s, methods:%s, headers:%s)", corsEnabled, strings.Join(cors.allowOrigins, ","), cors.allowMethods, cors.allowHeaders),
+			fmt.Sprintf("rateLimit(rate:%s, burst:%d)", strconv.FormatFloat(rateLimit, 'g', -1, 64), rateBurst),
+			"rewriteRules: " + strings.Join(rules, "; "),
+			"pathHeaders: " + strings.Join(headers, "; "),
+			"trustedProxies: " + formatCIDRs(trustedProxies),
+			fmt.Sprintf("ip(allow:%s, deny:%s)", formatCIDRs(allowIPs), formatCIDRs(denyIPs)),
+			fmt.Sprintf("ext(allow:%s, deny:%s)", strings.Join(allowExts, ","), strings.Join(denyExts, ",")),
+			"basicAuth: " + strconv.FormatBool(basicAuth != ""),
+			"notFoundPage: " + notFoundPage,
+			fmt.Sprintf("timeout(header:%s, keepalive:%s)", e.Server.ReadHeaderTimeout, e.Server.IdleTimeout),
+		}
+		for _, item := range summary {
+			log.Println(item)
+		}
+		err := checkStorage(staticPath, indexFiles)
+		if err != nil {
+			log.Fatal(err.Error())
+		}
+		if notFoundPage != "" {
+			_, err = os.Stat(filepath.Join(staticPath, notFoundPage))
+			if err != nil {
+				log.Fatal("not found page is invalid, " + err.Error())
+			}
+		}
+		if tlsCert != "" {
+			_, err = tls.LoadX509KeyPair(tlsCert, tlsKey)
+			if err != nil {
+				log.Fatal("tls cert or key is invalid, " + err.Error())
+			}
+		}
+		log.Println("config is valid")
+		return
+	}
 	log.Println("server is running")
 
-	err := e.ListenAndServe(":3000")
-	if err != nil {
+	done := make(chan struct{})
+	go func() {
+		ch := make(chan os.Signal, 1)
+		signal.Notify(ch, syscall.SIGINT, syscall.SIGTERM)
+		<-ch
+		// 先设置为关闭中，健康检查失败后再延时关闭
+		atomic.StoreInt32(&closing, 1)
+		log.Printf("server will be closed after %s", shutdownDelay)
+		time.Sleep(shutdownDelay)
+		err := e.Server.Shutdown(context.Background())
+		if err != nil {
+			log.Println("shutdown fail, " + err.Error())
+		}
+		close(done)
+	}()
+
+	errs := make(chan error, len(listenAddrs))
+	for _, addr := range listenAddrs {
+		l, err := listen(addr)
+		if err != nil {
+			panic(err)
+		}
+		go func() {
+			if tlsCert != "" {
+				errs <- e.Server.ServeTLS(l, tlsCert, tlsKey)
+			} else {
+				errs <- e.Server.Serve(l)
+			}
+		}()
+	}
+	err = <-errs
+	if err != nil && err != http.ErrServerClosed {
 		panic(err)
 	}
+	<-done
 }
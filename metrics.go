@@ -0,0 +1,141 @@
+package main
+
+import (
+	"bytes"
+	"fmt"
+	"io"
+	"net/http"
+	"sort"
+	"strconv"
+	"sync"
+	"time"
+
+	"github.com/vicanso/elton"
+)
+
+// 请求耗时分布(秒)
+var durationBuckets = []float64{0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1, 2.5, 5, 10}
+
+type httpMetrics struct {
+	mutex    sync.Mutex
+	statuses map[int]uint64
+	buckets  []uint64
+	count    uint64
+	sum      float64
+	bytes    uint64
+}
+
+func newHTTPMetrics() *httpMetrics {
+	return &httpMetrics{
+		statuses: make(map[int]uint64),
+		buckets:  make([]uint64, len(durationBuckets)),
+	}
+}
+
+func (m *httpMetrics) observe(status, size int, seconds float64) {
+	m.mutex.Lock()
+	defer m.mutex.Unlock()
+	m.statuses[status]++
+	for i, le := range durationBuckets {
+		if seconds <= le {
+			m.buckets[i]++
+		}
+	}
+	m.count++
+	m.sum += seconds
+	if size > 0 {
+		m.bytes += uint64(size)
+	}
+}
+
+// 以prometheus的文本格式输出
+func (m *httpMetrics) writeTo(w io.Writer) {
+	m.mutex.Lock()
+	defer m.mutex.Unlock()
+	fmt.Fprintln(w, "# HELP static_http_requests_total Total number of http requests by status.")
+	fmt.Fprintln(w, "# TYPE static_http_requests_total counter")
+	statuses := make([]int, 0, len(m.statuses))
+	for status := range m.statuses {
+		statuses = append(statuses, status)
+	}
+	sort.Ints(statuses)
+	for _, status := range statuses {
+		fmt.Fprintf(w, "static_http_requests_total{status=\"%d\"} %d\n", status, m.statuses[status])
+	}
+	fmt.Fprintln(w, "# HELP static_http_request_duration_seconds Http request duration in seconds.")
+	fmt.Fprintln(w, "# TYPE static_http_request_duration_seconds histogram")
+	for i, le := range durationBuckets {
+		fmt.Fprintf(w, "static_http_request_duration_seconds_bucket{le=\"%s\"} %d\n", strconv.FormatFloat(le, 'g', -1, 64), m.buckets[i])
+	}
+	fmt.Fprintf(w, "static_http_request_duration_seconds_bucket{le=\"+Inf\"} %d\n", m.count)
+	fmt.Fprintf(w, "static_http_request_duration_seconds_sum %s\n", strconv.FormatFloat(m.sum, 'g', -1, 64))
+	fmt.Fprintf(w, "static_http_request_duration_seconds_count %d\n", m.count)
+	fmt.Fprintln(w, "# HELP static_http_response_bytes_total Total bytes of http response body.")
+	fmt.Fprintln(w, "# TYPE static_http_response_bytes_total counter")
+	fmt.Fprintf(w, "static_http_response_bytes_total %d\n", m.bytes)
+}
+
+// 记录实际输出的状态码与数据长度
+type metricsResponseWriter struct {
+	http.ResponseWriter
+	status int
+	size   int
+}
+
+func (w *metricsResponseWriter) WriteHeader(status int) {
+	if w.status == 0 {
+		w.status = status
+	}
+	w.ResponseWriter.WriteHeader(status)
+}
+
+func (w *metricsResponseWriter) Write(b []byte) (int, error) {
+	if w.status == 0 {
+		w.status = http.StatusOK
+	}
+	n, err := w.ResponseWriter.Write(b)
+	w.size += n
+	return n, err
+}
+
+// 保留ResponseWriter的ReadFrom(文件可使用sendfile输出)
+func (w *metricsResponseWriter) ReadFrom(r io.Reader) (int64, error) {
+	if w.status == 0 {
+		w.status = http.StatusOK
+	}
+	n, err := io.Copy(w.ResponseWriter, r)
+	w.size += int(n)
+	return n, err
+}
+
+// 在elton之外统计请求数、状态码、耗时与响应数据长度，包括响应数据的输出，内部接口不统计
+func newMetricsCollector(handler http.Handler, m *httpMetrics) http.Handler {
+	return http.HandlerFunc(func(w http.ResponseWriter, r *http.Request) {
+		if isInternalRequest(r) {
+			handler.ServeHTTP(w, r)
+			return
+		}
+		start := time.Now()
+		mw := &metricsResponseWriter{
+			ResponseWriter: w,
+		}
+		handler.ServeHTTP(mw, r)
+		status := mw.status
+		if status == 0 {
+			status = http.StatusOK
+		}
+		m.observe(status, mw.size, time.Since(start).Seconds())
+	})
+}
+
+// prometheus采集接口
+func newMetricsHandler(m *httpMetrics) elton.Handler {
+	return func(c *elton.Context) error {
+		buf := new(bytes.Buffer)
+		m.writeTo(buf)
+		c.NoCache()
+		c.SetHeader("Content-Type", "text/plain; version=0.0.4; charset=utf-8")
+		c.BodyBuffer = buf
+		return nil
+	}
+}
@@ -0,0 +1,680 @@
+package main
+
+import (
+	"bytes"
+	"context"
+	"crypto/rand"
+	"crypto/subtle"
+	"encoding/hex"
+	"encoding/json"
+	"fmt"
+	"io"
+	"net/http"
+	"net/url"
+	"os"
+	"path"
+	"path/filepath"
+	"sort"
+	"strconv"
+	"strings"
+	"sync"
+	"time"
+
+	"github.com/allegro/bigcache/v3"
+	"github.com/vicanso/elton"
+	"github.com/vicanso/hes"
+)
+
+const headerRequestID = "X-Request-Id"
+
+var (
+	errInvalidPath  = hes.NewWithStatusCode("invalid file path", http.StatusBadRequest)
+	errFileNotFound = hes.NewWithStatusCode("static file not found", http.StatusNotFound)
+)
+
+func isNotFoundError(err error) bool {
+	he, ok := err.(*hes.Error)
+	return ok && he.StatusCode == http.StatusNotFound
+}
+
+// 获取请求文件的扩展名(小写且不含.)，目录则为首页html
+func getRequestExt(c *elton.Context) string {
+	p := c.Request.URL.Path
+	if strings.HasSuffix(p, "/") {
+		return "html"
+	}
+	return strings.ToLower(strings.TrimPrefix(path.Ext(p), "."))
+}
+
+// 已注册的内部接口，格式为"METHOD path"
+var internalRoutes = make(map[string]bool)
+
+func addInternalRoute(method, p string) {
+	internalRoutes[method+" "+p] = true
+}
+
+// 是否请求已注册的内部接口，未注册的路径由静态文件处理
+func isInternalRoute(c *elton.Context) bool {
+	return isInternalRequest(c.Request)
+}
+
+func isInternalRequest(req *http.Request) bool {
+	return internalRoutes[req.Method+" "+req.URL.Path]
+}
+
+// HEAD响应头缓存的key前缀
+const headMetadataPrefix = "HEAD-META "
+
+// HEAD请求只返回响应头，不输出文件内容。
+// GET与HEAD成功的响应头会被缓存，HEAD请求命中时直接返回缓存的响应头，不再读取文件；
+// 未命中时使用强ETag的文件需读取内容计算ETag，以流方式返回的文件只获取文件信息
+func newHeadMiddleware(staticPath string, cache *bigcache.BigCache) elton.Handler {
+	return func(c *elton.Context) error {
+		method := c.Request.Method
+		cacheable := (method == http.MethodGet || method == http.MethodHead) && !isInternalRoute(c)
+		key := headMetadataPrefix + c.Request.RequestURI
+		if method == http.MethodHead && cacheable {
+			header := make(http.Header)
+			buf, err := cache.Get(key)
+			if err == nil && json.Unmarshal(buf, &header) == nil {
+				for name, values := range header {
+					c.Header()[name] = values
+				}
+				return nil
+			}
+		}
+		err := c.Next()
+		if err != nil {
+			return err
+		}
+		// Content-Length以响应数据长度为准，以流方式返回的文件则使用文件大小
+		contentLength := c.GetHeader("Content-Length")
+		if c.BodyBuffer != nil {
+			contentLength = strconv.Itoa(c.BodyBuffer.Len())
+		} else if c.Body != nil && contentLength == "" {
+			var info os.FileInfo
+			var e error
+			if f, ok := c.Body.(interface{ Stat() (os.FileInfo, error) }); ok {
+				info, e = f.Stat()
+			} else {
+				info, e = os.Stat(filepath.Join(staticPath, c.Request.URL.Path))
+			}
+			if e == nil && !info.IsDir() {
+				contentLength = strconv.FormatInt(info.Size(), 10)
+			}
+		}
+		status := c.StatusCode
+		// 压缩后或存在预压缩文件(Vary)的响应头与客户端相关，不缓存
+		if cacheable && (status == 0 || status == http.StatusOK) &&
+			contentLength != "" && c.GetHeader("Content-Encoding") == "" &&
+			c.GetHeader("Vary") == "" {
+			header := c.Header().Clone()
+			header.Set("Content-Length", contentLength)
+			if buf, e := json.Marshal(header); e == nil {
+				_ = cache.Set(key, buf)
+			}
+		}
+		if method != http.MethodHead {
+			return nil
+		}
+		if contentLength != "" {
+			c.SetHeader("Content-Length", contentLength)
+		}
+		c.BodyBuffer = nil
+		if r, ok := c.Body.(io.Closer); ok {
+			_ = r.Close()
+		}
+		c.Body = nil
+		return nil
+	}
+}
+
+// 文件不存在时返回自定义的404页面，若页面加载失败则返回原有出错信息
+func newNotFoundPageMiddleware(file string) elton.Handler {
+	return func(c *elton.Context) error {
+		err := c.Next()
+		if err == nil || !isNotFoundError(err) {
+			return err
+		}
+		buf, e := os.ReadFile(file)
+		if e != nil {
+			return err
+		}
+		c.NoCache()
+		c.SetContentTypeByExt(".html")
+		c.StatusCode = http.StatusNotFound
+		c.BodyBuffer = bytes.NewBuffer(buf)
+		return nil
+	}
+}
+
+// 基础认证，健康检查与已注册的管理接口(使用token校验)不需要校验
+func newBasicAuthMiddleware(account, password, realm string) elton.Handler {
+	wwwAuthenticate := fmt.Sprintf(`Basic realm="%s"`, realm)
+	return func(c *elton.Context) error {
+		p := c.Request.URL.Path
+		if isInternalRoute(c) && (p == "/ping" || strings.HasPrefix(p, "/-/")) {
+			return c.Next()
+		}
+		user, pwd, ok := c.Request.BasicAuth()
+		// 账号密码均需比较，避免耗时差异
+		userMatched := subtle.ConstantTimeCompare([]byte(user), []byte(account))
+		pwdMatched := subtle.ConstantTimeCompare([]byte(pwd), []byte(password))
+		if ok && userMatched&pwdMatched == 1 {
+			return c.Next()
+		}
+		c.SetHeader("WWW-Authenticate", wwwAuthenticate)
+		return hes.NewWithStatusCode("unauthorized", http.StatusUnauthorized)
+	}
+}
+
+// 校验路径，禁止包含空字符以及通过..跳出静态文件目录
+func checkPath(p string) error {
+	if strings.ContainsRune(p, 0) {
+		return errInvalidPath
+	}
+	items := strings.FieldsFunc(p, func(r rune) bool {
+		return r == '/' || r == '\\'
+	})
+	for _, item := range items {
+		if item == ".." {
+			return errInvalidPath
+		}
+	}
+	return nil
+}
+
+// 校验请求路径(已解码)
+func newPathCheckMiddleware() elton.Handler {
+	return func(c *elton.Context) error {
+		err := checkPath(c.Request.URL.Path)
+		if err != nil {
+			return err
+		}
+		return c.Next()
+	}
+}
+
+// 请求ID，优先使用请求头中的X-Request-Id，没有则生成
+// 在处理完成后才设置，避免被缓存中间件保存到缓存的响应头中
+func newRequestIDMiddleware() elton.Handler {
+	return func(c *elton.Context) error {
+		id := c.GetRequestHeader(headerRequestID)
+		if id == "" {
+			buf := make([]byte, 16)
+			_, _ = rand.Read(buf)
+			id = hex.EncodeToString(buf)
+		}
+		err := c.Next()
+		c.SetHeader(headerRequestID, id)
+		return err
+	}
+}
+
+// 按文件扩展名设置Cache-Control，只针对成功的响应，避免404等页面被长期缓存
+func newCacheControlMiddleware(cacheControls map[string]string) elton.Handler {
+	return func(c *elton.Context) error {
+		err := c.Next()
+		if err != nil {
+			return err
+		}
+		status := c.StatusCode
+		if status != 0 && status != http.StatusNotModified && (status < 200 || status >= 300) {
+			return nil
+		}
+		value, ok := cacheControls[getRequestExt(c)]
+		if ok {
+			c.SetHeader("Cache-Control", value)
+		}
+		return nil
+	}
+}
+
+// 按扩展名限制访问，返回404避免暴露文件是否存在
+func newExtFilterMiddleware(allowExts, denyExts []string) elton.Handler {
+	return func(c *elton.Context) error {
+		if isInternalRoute(c) {
+			return c.Next()
+		}
+		ext := getRequestExt(c)
+		for _, item := range denyExts {
+			if item == ext {
+				return errFileNotFound
+			}
+		}
+		if len(allowExts) == 0 {
+			return c.Next()
+		}
+		for _, item := range allowExts {
+			if item == ext {
+				return c.Next()
+			}
+		}
+		return errFileNotFound
+	}
+}
+
+// 安全相关响应头，已设置的响应头不覆盖
+func newSecurityHeadersMiddleware(csp string) elton.Handler {
+	headers := map[string]string{
+		"X-Content-Type-Options": "nosniff",
+		"X-Frame-Options":        "SAMEORIGIN",
+	}
+	if csp != "" {
+		headers["Content-Security-Policy"] = csp
+	}
+	return func(c *elton.Context) error {
+		err := c.Next()
+		for key, value := range headers {
+			if c.GetHeader(key) == "" {
+				c.SetHeader(key, value)
+			}
+		}
+		return err
+	}
+}
+
+// 生成下载的Content-Disposition，非ASCII文件名使用filename*
+func attachmentDisposition(name string) string {
+	fallback := []rune(name)
+	for i, r := range fallback {
+		if r > 127 || r == '"' || r == '\\' {
+			fallback[i] = '_'
+		}
+	}
+	escaped := strings.ReplaceAll(url.QueryEscape(name), "+", "%20")
+	return fmt.Sprintf(`attachment; filename="%s"; filename*=UTF-8''%s`, string(fallback), escaped)
+}
+
+// 指定扩展名的文件或请求参数download=1时以附件形式下载
+func newAttachmentMiddleware(exts []string) elton.Handler {
+	return func(c *elton.Context) error {
+		err := c.Next()
+		if err != nil || isInternalRoute(c) {
+			return err
+		}
+		download := c.QueryParam("download") == "1"
+		if !download {
+			ext := getRequestExt(c)
+			for _, item := range exts {
+				if item == ext {
+					download = true
+					break
+				}
+			}
+		}
+		name := path.Base(c.Request.URL.Path)
+		if download && name != "/" {
+			c.SetHeader("Content-Disposition", attachmentDisposition(name))
+		}
+		return nil
+	}
+}
+
+// 软链接校验，不允许时软链接视为文件不存在，允许时也不能指向静态目录之外
+func newSymlinkMiddleware(staticPath string, indexFiles []string, follow bool) elton.Handler {
+	root, err := filepath.EvalSymlinks(staticPath)
+	if err != nil {
+		root = filepath.Clean(staticPath)
+	}
+	return func(c *elton.Context) error {
+		p := c.Request.URL.Path
+		method := c.Request.Method
+		if isInternalRoute(c) || (method != http.MethodGet && method != http.MethodHead) {
+			return c.Next()
+		}
+		file := filepath.Join(root, p)
+		if p == "/" {
+			file, _ = findIndexFile(root, indexFiles)
+			if file == "" {
+				return c.Next()
+			}
+		}
+		resolved, err := filepath.EvalSymlinks(file)
+		if err != nil {
+			return errFileNotFound
+		}
+		if follow {
+			if resolved != root && !strings.HasPrefix(resolved, root+string(filepath.Separator)) {
+				return errFileNotFound
+			}
+		} else if resolved != file {
+			return errFileNotFound
+		}
+		return c.Next()
+	}
+}
+
+type dirNames struct {
+	names     []string
+	expiredAt time.Time
+}
+
+// 目录下文件名的短时缓存，避免不存在的文件每次都读取目录
+type dirCache struct {
+	mutex sync.Mutex
+	ttl   time.Duration
+	dirs  map[string]*dirNames
+}
+
+func newDirCache(ttl time.Duration) *dirCache {
+	dc := &dirCache{
+		ttl:  ttl,
+		dirs: make(map[string]*dirNames),
+	}
+	go func() {
+		for range time.Tick(time.Minute) {
+			dc.evict()
+		}
+	}()
+	return dc
+}
+
+// 获取目录下的文件名，读取失败(如目录不存在)的不缓存
+func (dc *dirCache) names(dir string) ([]string, error) {
+	dc.mutex.Lock()
+	item, ok := dc.dirs[dir]
+	dc.mutex.Unlock()
+	if ok && time.Now().Before(item.expiredAt) {
+		return item.names, nil
+	}
+	entries, err := os.ReadDir(dir)
+	if err != nil {
+		return nil, err
+	}
+	names := make([]string, len(entries))
+	for i, entry := range entries {
+		names[i] = entry.Name()
+	}
+	dc.mutex.Lock()
+	dc.dirs[dir] = &dirNames{
+		names:     names,
+		expiredAt: time.Now().Add(dc.ttl),
+	}
+	dc.mutex.Unlock()
+	return names, nil
+}
+
+// 删除已过期的缓存，避免内存持续增长
+func (dc *dirCache) evict() {
+	dc.mutex.Lock()
+	defer dc.mutex.Unlock()
+	now := time.Now()
+	for dir, item := range dc.dirs {
+		if now.After(item.expiredAt) {
+			delete(dc.dirs, dir)
+		}
+	}
+}
+
+// 文件不存在时忽略大小写查找所在目录下的同名文件，目录下的文件名缓存10秒
+func newCaseInsensitiveMiddleware(staticPath string) elton.Handler {
+	dc := newDirCache(10 * time.Second)
+	return func(c *elton.Context) error {
+		p := c.Request.URL.Path
+		if isInternalRoute(c) || strings.HasSuffix(p, "/") {
+			return c.Next()
+		}
+		file := filepath.Join(staticPath, p)
+		_, err := os.Stat(file)
+		if err == nil || !os.IsNotExist(err) {
+			return c.Next()
+		}
+		names, err := dc.names(filepath.Dir(file))
+		if err != nil {
+			return c.Next()
+		}
+		name := filepath.Base(file)
+		for _, item := range names {
+			if strings.EqualFold(item, name) {
+				c.Request.URL.Path = path.Join(path.Dir(p), item)
+				break
+			}
+		}
+		return c.Next()
+	}
+}
+
+type pathHeader struct {
+	prefix string
+	name   string
+	value  string
+}
+
+// 解析以STATIC_PATH_HEADER_开头的环境变量，格式为prefix:name=value
+func parsePathHeaders(environ []string) []pathHeader {
+	headers := make([]pathHeader, 0)
+	for _, env := range environ {
+		if !strings.HasPrefix(env, "STATIC_PATH_HEADER_") {
+			continue
+		}
+		value := env[strings.Index(env, "=")+1:]
+		arr := strings.SplitN(value, ":", 2)
+		if len(arr) != 2 {
+			continue
+		}
+		kv := strings.SplitN(arr[1], "=", 2)
+		if len(kv) != 2 {
+			continue
+		}
+		headers = append(headers, pathHeader{
+			prefix: strings.TrimSpace(arr[0]),
+			name:   strings.TrimSpace(kv[0]),
+			value:  strings.TrimSpace(kv[1]),
+		})
+	}
+	// 前缀较长的后设置，同名响应头以最长匹配为准
+	sort.SliceStable(headers, func(i, j int) bool {
+		return len(headers[i].prefix) < len(headers[j].prefix)
+	})
+	return headers
+}
+
+// 按路径分段匹配前缀，/fonts匹配/fonts与/fonts/a.woff，不匹配/fontsx
+func matchPathPrefix(p, prefix string) bool {
+	prefix = strings.TrimSuffix(prefix, "/")
+	return prefix == "" || p == prefix || strings.HasPrefix(p, prefix+"/")
+}
+
+// 按请求路径前缀设置响应头
+func newPathHeaderMiddleware(headers []pathHeader) elton.Handler {
+	return func(c *elton.Context) error {
+		err := c.Next()
+		p := c.Request.URL.Path
+		for _, header := range headers {
+			if matchPathPrefix(p, header.prefix) {
+				c.SetHeader(header.name, header.value)
+			}
+		}
+		return err
+	}
+}
+
+type cacheStatusKey struct{}
+
+// 缓存处理状态，由缓存中间件前后的中间件记录
+type cacheStatus struct {
+	// 缓存未命中，缓存中间件之后的处理有执行
+	fetched bool
+	// 读取文件的耗时
+	fetch time.Duration
+	// 以流的方式返回，响应数据未被缓存
+	streamed bool
+}
+
+// 记录请求已到达缓存中间件，需在缓存中间件之前添加
+func newCacheStatusMiddleware() elton.Handler {
+	return func(c *elton.Context) error {
+		cs := &cacheStatus{}
+		c.Request = c.Request.WithContext(context.WithValue(c.Request.Context(), cacheStatusKey{}, cs))
+		return c.Next()
+	}
+}
+
+// 记录缓存未命中时读取文件的耗时，需在缓存中间件之后添加，缓存命中时不会执行
+func newCacheMissMiddleware() elton.Handler {
+	return func(c *elton.Context) error {
+		cs := getCacheStatus(c)
+		if cs == nil {
+			return c.Next()
+		}
+		start := time.Now()
+		err := c.Next()
+		cs.fetched = true
+		cs.fetch = time.Since(start)
+		cs.streamed = c.BodyBuffer == nil && c.Body != nil
+		return err
+	}
+}
+
+// 获取缓存处理状态，请求未到达缓存中间件(如认证失败、被过滤等)时返回nil
+func getCacheStatus(c *elton.Context) *cacheStatus {
+	cs, _ := c.Request.Context().Value(cacheStatusKey{}).(*cacheStatus)
+	return cs
+}
+
+// 添加X-Cache响应头，缓存命中为HIT，未命中为MISS，
+// 以流的方式返回(不缓存)或未经过缓存(缓存关闭、请求在此之前已返回)的为BYPASS
+func newCacheStatusHeaderMiddleware() elton.Handler {
+	return func(c *elton.Context) error {
+		err := c.Next()
+		value := "BYPASS"
+		if cs := getCacheStatus(c); cs != nil {
+			if !cs.fetched {
+				value = "HIT"
+			} else if !cs.streamed {
+				value = "MISS"
+			}
+		}
+		c.SetHeader("X-Cache", value)
+		return err
+	}
+}
+
+// 添加Server-Timing响应头，记录缓存是否命中、读取文件与服务端处理耗时(ms)，
+// 请求未到达缓存中间件时不记录缓存状态，
+// 响应数据在所有中间件执行完成后才输出，因此耗时不包括数据输出
+func newServerTimingMiddleware() elton.Handler {
+	return func(c *elton.Context) error {
+		start := time.Now()
+		err := c.Next()
+		values := make([]string, 0, 3)
+		if cs := getCacheStatus(c); cs != nil {
+			if cs.fetched {
+				values = append(values, `cache;desc="MISS"`)
+				values = append(values, fmt.Sprintf("fetch;dur=%.2f", float64(cs.fetch.Microseconds())/1000))
+			} else {
+				values = append(values, `cache;desc="HIT"`)
+			}
+		}
+		ms := float64(time.Since(start).Microseconds()) / 1000
+		values = append(values, fmt.Sprintf("app;dur=%.2f", ms))
+		c.SetHeader("Server-Timing", strings.Join(values, ", "))
+		return err
+	}
+}
+
+// 缓存的响应头，用于metadata缓存模式
+type cachedMetadata struct {
+	ETag         string `json:"etag"`
+	LastModified string `json:"lastModified,omitempty"`
+	CacheControl string `json:"cacheControl,omitempty"`
+	ContentType  string `json:"contentType,omitempty"`
+}
+
+// 只缓存响应头不缓存响应数据，If-None-Match与缓存的ETag一致时直接返回304，不再读取文件
+func newMetadataCacheMiddleware(cache *bigcache.BigCache) elton.Handler {
+	return func(c *elton.Context) error {
+		method := c.Request.Method
+		if method != http.MethodGet && method != http.MethodHead {
+			return c.Next()
+		}
+		// 与完整缓存的key一致，可通过管理接口清除
+		key := method + " " + c.Request.RequestURI
+		if ifNoneMatch := c.GetRequestHeader("If-None-Match"); ifNoneMatch != "" {
+			meta := cachedMetadata{}
+			buf, err := cache.Get(key)
+			if err == nil && json.Unmarshal(buf, &meta) == nil && meta.ETag == ifNoneMatch {
+				c.SetHeader("ETag", meta.ETag)
+				if meta.LastModified != "" {
+					c.SetHeader("Last-Modified", meta.LastModified)
+				}
+				if meta.CacheControl != "" {
+					c.SetHeader("Cache-Control", meta.CacheControl)
+				}
+				if meta.ContentType != "" {
+					c.SetHeader("Content-Type", meta.ContentType)
+				}
+				c.StatusCode = http.StatusNotModified
+				return nil
+			}
+		}
+		err := c.Next()
+		if err != nil {
+			return err
+		}
+		etag := c.GetHeader("ETag")
+		if etag == "" || (c.StatusCode != 0 && c.StatusCode != http.StatusOK) {
+			return nil
+		}
+		buf, err := json.Marshal(&cachedMetadata{
+			ETag:         etag,
+			LastModified: c.GetHeader("Last-Modified"),
+			CacheControl: c.GetHeader("Cache-Control"),
+			ContentType:  c.GetHeader("Content-Type"),
+		})
+		if err == nil {
+			_ = cache.Set(key, buf)
+		}
+		return nil
+	}
+}
+
+// 静态文件服务只支持的请求方法
+const allowMethods = "GET, HEAD, OPTIONS"
+
+// 已注册路由的请求方法，其它方法elton无法匹配路由
+var routedMethods = map[string]bool{
+	http.MethodGet:     true,
+	http.MethodHead:    true,
+	http.MethodOptions: true,
+	http.MethodPost:    true,
+	http.MethodPut:     true,
+	http.MethodPatch:   true,
+	http.MethodDelete:  true,
+}
+
+type originalMethodKey struct{}
+
+// 返回405，不允许缓存，如果请求方法由newMethodRouteHandler转换则恢复原始的请求方法，
+// 访问日志中记录的为原始方法
+func newMethodNotAllowedHandler() elton.Handler {
+	return func(c *elton.Context) error {
+		if method, ok := c.Request.Context().Value(originalMethodKey{}).(string); ok {
+			c.Request.Method = method
+		}
+		c.NoCache()
+		c.SetHeader("Allow", allowMethods)
+		return hes.NewWithStatusCode("method not allowed", http.StatusMethodNotAllowed)
+	}
+}
+
+// 未注册路由的请求方法(如TRACE、CONNECT或自定义方法)转为DELETE，
+// 由405的路由处理，保证同样经过中间件(访问日志等)
+func newMethodRouteHandler(handler http.Handler) http.Handler {
+	return http.HandlerFunc(func(w http.ResponseWriter, r *http.Request) {
+		if routedMethods[r.Method] {
+			handler.ServeHTTP(w, r)
+			return
+		}
+		req := r.WithContext(context.WithValue(r.Context(), originalMethodKey{}, r.Method))
+		req.Method = http.MethodDelete
+		// CONNECT请求的路径为空
+		if req.URL.Path == "" {
+			u := *req.URL
+			u.Path = "/"
+			req.URL = &u
+		}
+		handler.ServeHTTP(w, req)
+	})
+}
@@ -0,0 +1,131 @@
+package main
+
+import (
+	"bytes"
+	"net/http"
+	"net/http/httptest"
+	"net/url"
+	"strings"
+	"testing"
+
+	"github.com/vicanso/elton"
+)
+
+func TestCheckPath(t *testing.T) {
+	tests := []struct {
+		uri   string
+		valid bool
+	}{
+		{"/", true},
+		{"/index.html", true},
+		{"/a/b..c/d.js", true},
+		{"/.../a.js", true},
+		{"/..", false},
+		{"/a/../../etc/passwd", false},
+		// 编码后的..
+		{"/%2e%2e/etc/passwd", false},
+		{"/a/%2E%2E/b", false},
+		{"/a%2f..%2fb", false},
+		// 混用/与\
+		{"/a%5c..%5cb", false},
+		{"/a/..%5cb", false},
+		{"/a%5c../b", false},
+		// 空字符
+		{"/a%00.html", false},
+		{"/index.html%00.js", false},
+	}
+	for _, tt := range tests {
+		u, err := url.Parse(tt.uri)
+		if err != nil {
+			t.Fatalf("parse %s fail, %v", tt.uri, err)
+		}
+		err = checkPath(u.Path)
+		if (err == nil) != tt.valid {
+			t.Errorf("check path %s, valid: %v, error: %v", tt.uri, tt.valid, err)
+		}
+	}
+}
+
+func TestAttachmentDisposition(t *testing.T) {
+	tests := []struct {
+		name   string
+		result string
+	}{
+		{
+			"report.csv",
+			`attachment; filename="report.csv"; filename*=UTF-8''report.csv`,
+		},
+		{
+			"报告 2022.csv",
+			`attachment; filename="__ 2022.csv"; filename*=UTF-8''%E6%8A%A5%E5%91%8A%202022.csv`,
+		},
+		{
+			`a"b\c.txt`,
+			`attachment; filename="a_b_c.txt"; filename*=UTF-8''a%22b%5Cc.txt`,
+		},
+	}
+	for _, tt := range tests {
+		result := attachmentDisposition(tt.name)
+		if result != tt.result {
+			t.Errorf("attachment disposition of %s, expected: %s, got: %s", tt.name, tt.result, result)
+		}
+	}
+}
+
+func TestMatchPathPrefix(t *testing.T) {
+	tests := []struct {
+		p       string
+		prefix  string
+		matched bool
+	}{
+		{"/fonts", "/fonts", true},
+		{"/fonts/a.woff", "/fonts", true},
+		{"/fonts/a.woff", "/fonts/", true},
+		{"/fontsx/a.woff", "/fonts", false},
+		{"/fonts.css", "/fonts", false},
+		{"/index.html", "/", true},
+	}
+	for _, tt := range tests {
+		if matchPathPrefix(tt.p, tt.prefix) != tt.matched {
+			t.Errorf("%s with prefix %s, expected matched: %v", tt.p, tt.prefix, tt.matched)
+		}
+	}
+}
+
+func TestMethodNotAllowed(t *testing.T) {
+	e := elton.New()
+	method := ""
+	e.Use(func(c *elton.Context) error {
+		err := c.Next()
+		method = c.Request.Method
+		return err
+	})
+	e.GET("/*", func(c *elton.Context) error {
+		c.BodyBuffer = bytes.NewBufferString("ok")
+		return nil
+	})
+	e.DELETE("/*", newMethodNotAllowedHandler())
+	handler := newMethodRouteHandler(e)
+	for _, m := range []string{http.MethodDelete, http.MethodTrace, "PURGE"} {
+		resp := httptest.NewRecorder()
+		handler.ServeHTTP(resp, httptest.NewRequest(m, "/index.html", nil))
+		if resp.Code != http.StatusMethodNotAllowed {
+			t.Errorf("%s, expected status 405, got %d", m, resp.Code)
+		}
+		if resp.Header().Get("Allow") != allowMethods {
+			t.Errorf("%s, expected allow header %s, got %s", m, allowMethods, resp.Header().Get("Allow"))
+		}
+		if !strings.Contains(resp.Header().Get("Cache-Control"), "no-cache") {
+			t.Errorf("%s, 405 response should not be cached", m)
+		}
+		// 中间件中获取的为原始的请求方法
+		if method != m {
+			t.Errorf("expected method %s in middleware, got %s", m, method)
+		}
+	}
+	resp := httptest.NewRecorder()
+	handler.ServeHTTP(resp, httptest.NewRequest(http.MethodGet, "/index.html", nil))
+	if resp.Code != http.StatusOK {
+		t.Errorf("GET should not be changed, got %d", resp.Code)
+	}
+}
@@ -0,0 +1,135 @@
+package main
+
+import (
+	"fmt"
+	"net/http"
+	"os"
+	"path"
+	"path/filepath"
+	"strconv"
+	"strings"
+
+	"github.com/vicanso/elton"
+)
+
+type precompressedVariant struct {
+	encoding string
+	ext      string
+}
+
+// 预压缩文件，q值相同时按此顺序优先
+var precompressedVariants = []precompressedVariant{
+	{
+		encoding: "zstd",
+		ext:      ".zst",
+	},
+	{
+		encoding: "br",
+		ext:      ".br",
+	},
+	{
+		encoding: "gzip",
+		ext:      ".gz",
+	},
+}
+
+// 解析Accept-Encoding，返回各编码的q值
+func parseAcceptEncoding(value string) map[string]float64 {
+	result := make(map[string]float64)
+	for _, item := range strings.Split(value, ",") {
+		arr := strings.Split(item, ";")
+		encoding := strings.ToLower(strings.TrimSpace(arr[0]))
+		if encoding == "" {
+			continue
+		}
+		q := 1.0
+		for _, param := range arr[1:] {
+			param = strings.TrimSpace(param)
+			if strings.HasPrefix(param, "q=") {
+				v, err := strconv.ParseFloat(strings.TrimPrefix(param, "q="), 64)
+				if err == nil {
+					q = v
+				}
+			}
+		}
+		result[encoding] = q
+	}
+	return result
+}
+
+// 客户端对该编码的q值，未声明时使用*的q值，返回0表示不接受
+func acceptEncodingQuality(accepts map[string]float64, encoding string) float64 {
+	q, ok := accepts[encoding]
+	if !ok {
+		q = accepts["*"]
+	}
+	return q
+}
+
+// 从已存在的预压缩文件中选择客户端q值最高的编码，q值相同时按precompressedVariants的顺序
+func selectPrecompressedVariant(accepts map[string]float64, variants []precompressedVariant) (precompressedVariant, bool) {
+	var result precompressedVariant
+	maxQuality := 0.0
+	for _, variant := range variants {
+		q := acceptEncodingQuality(accepts, variant.encoding)
+		if q > maxQuality {
+			result = variant
+			maxQuality = q
+		}
+	}
+	return result, maxQuality > 0
+}
+
+// 存在预压缩文件(file.zst、file.br、file.gz)且客户端支持时直接返回q值最高的文件，
+// 需在缓存中间件之前，预压缩的响应与客户端相关，不经过缓存
+func newPrecompressedMiddleware(staticPath, cacheControl string) elton.Handler {
+	return func(c *elton.Context) error {
+		p := c.Request.URL.Path
+		method := c.Request.Method
+		if (method != http.MethodGet && method != http.MethodHead) ||
+			isInternalRoute(c) || strings.HasSuffix(p, "/") {
+			return c.Next()
+		}
+		// 隐藏文件由静态文件中间件处理(禁止访问)
+		for _, item := range strings.Split(p, "/") {
+			if strings.HasPrefix(item, ".") {
+				return c.Next()
+			}
+		}
+		file := filepath.Join(staticPath, p)
+		variants := make([]precompressedVariant, 0, len(precompressedVariants))
+		infos := make(map[string]os.FileInfo)
+		for _, variant := range precompressedVariants {
+			// 只使用普通文件，软链接的预压缩文件忽略
+			info, err := os.Lstat(file + variant.ext)
+			if err != nil || !info.Mode().IsRegular() {
+				continue
+			}
+			variants = append(variants, variant)
+			infos[variant.encoding] = info
+		}
+		accepts := parseAcceptEncoding(c.GetRequestHeader("Accept-Encoding"))
+		if variant, ok := selectPrecompressedVariant(accepts, variants); ok {
+			r, err := os.Open(file + variant.ext)
+			if err != nil {
+				return err
+			}
+			info := infos[variant.encoding]
+			c.SetContentTypeByExt(path.Ext(p))
+			c.SetHeader("Content-Encoding", variant.encoding)
+			c.Header().Add("Vary", "Accept-Encoding")
+			c.SetHeader("Last-Modified", info.ModTime().UTC().Format(http.TimeFormat))
+			c.SetHeader("ETag", fmt.Sprintf(`W/"%x-%x-%s"`, info.Size(), info.ModTime().Unix(), variant.encoding))
+			c.SetHeader("Cache-Control", cacheControl)
+			c.Body = r
+			return nil
+		}
+		err := c.Next()
+		// 有预压缩文件但客户端不支持时，响应同样与Accept-Encoding相关，
+		// 在处理完成后才设置，避免被缓存中间件保存到缓存的响应头中
+		if len(variants) != 0 {
+			c.Header().Add("Vary", "Accept-Encoding")
+		}
+		return err
+	}
+}
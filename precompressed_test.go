@@ -0,0 +1,57 @@
+package main
+
+import (
+	"testing"
+)
+
+func TestParseAcceptEncoding(t *testing.T) {
+	accepts := parseAcceptEncoding("gzip, BR;q=0.8, zstd;q=0, *;q=0.1")
+	tests := []struct {
+		encoding string
+		quality  float64
+	}{
+		{"gzip", 1},
+		{"br", 0.8},
+		{"zstd", 0},
+		// 未声明的编码使用*的q值
+		{"deflate", 0.1},
+	}
+	for _, tt := range tests {
+		if q := acceptEncodingQuality(accepts, tt.encoding); q != tt.quality {
+			t.Errorf("%s, expected q=%v, got %v", tt.encoding, tt.quality, q)
+		}
+	}
+	if q := acceptEncodingQuality(parseAcceptEncoding(""), "gzip"); q != 0 {
+		t.Errorf("empty accept encoding should not accept gzip, got q=%v", q)
+	}
+}
+
+func TestSelectPrecompressedVariant(t *testing.T) {
+	zstd := precompressedVariants[0]
+	br := precompressedVariants[1]
+	gzip := precompressedVariants[2]
+	tests := []struct {
+		acceptEncoding string
+		variants       []precompressedVariant
+		encoding       string
+	}{
+		// q值相同时zstd > br > gzip
+		{"gzip, br, zstd", precompressedVariants, "zstd"},
+		{"gzip, br", precompressedVariants, "br"},
+		{"gzip, br", []precompressedVariant{gzip}, "gzip"},
+		// 按q值选择
+		{"zstd;q=0.5, br;q=0.6, gzip", precompressedVariants, "gzip"},
+		{"zstd;q=0.5, br;q=0.6", []precompressedVariant{zstd, gzip}, "zstd"},
+		{"*", []precompressedVariant{br, gzip}, "br"},
+		// q=0为不接受
+		{"br;q=0, *", []precompressedVariant{br}, ""},
+		{"", precompressedVariants, ""},
+		{"gzip", []precompressedVariant{zstd, br}, ""},
+	}
+	for _, tt := range tests {
+		variant, ok := selectPrecompressedVariant(parseAcceptEncoding(tt.acceptEncoding), tt.variants)
+		if ok != (tt.encoding != "") || variant.encoding != tt.encoding {
+			t.Errorf("%q, expected %q, got %q", tt.acceptEncoding, tt.encoding, variant.encoding)
+		}
+	}
+}
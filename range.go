@@ -0,0 +1,158 @@
+package main
+
+import (
+	"bytes"
+	"fmt"
+	"io"
+	"net/http"
+	"strconv"
+	"strings"
+
+	"github.com/vicanso/elton"
+	"github.com/vicanso/hes"
+)
+
+var errRangeNotSatisfiable = hes.NewWithStatusCode("range not satisfiable", http.StatusRequestedRangeNotSatisfiable)
+
+// 解析Range请求头，只支持单个范围，多个范围或范围无效均返回416
+// 返回的start与end均包含在范围内
+func parseRange(value string, size int64) (int64, int64, error) {
+	if !strings.HasPrefix(value, "bytes=") || size <= 0 {
+		return 0, 0, errRangeNotSatisfiable
+	}
+	spec := strings.TrimSpace(strings.TrimPrefix(value, "bytes="))
+	if strings.Contains(spec, ",") {
+		return 0, 0, errRangeNotSatisfiable
+	}
+	arr := strings.SplitN(spec, "-", 2)
+	if len(arr) != 2 {
+		return 0, 0, errRangeNotSatisfiable
+	}
+	startValue := strings.TrimSpace(arr[0])
+	endValue := strings.TrimSpace(arr[1])
+	// bytes=-500 为最后500字节
+	if startValue == "" {
+		n, err := strconv.ParseInt(endValue, 10, 64)
+		if err != nil || n <= 0 {
+			return 0, 0, errRangeNotSatisfiable
+		}
+		if n > size {
+			n = size
+		}
+		return size - n, size - 1, nil
+	}
+	start, err := strconv.ParseInt(startValue, 10, 64)
+	if err != nil || start < 0 || start >= size {
+		return 0, 0, errRangeNotSatisfiable
+	}
+	end := size - 1
+	// bytes=500- 为从500字节至结束
+	if endValue != "" {
+		v, err := strconv.ParseInt(endValue, 10, 64)
+		if err != nil || v < start {
+			return 0, 0, errRangeNotSatisfiable
+		}
+		if v < end {
+			end = v
+		}
+	}
+	return start, end, nil
+}
+
+// If-Range为ETag时只有双方均为强ETag且一致才返回部分内容，为时间时需与Last-Modified一致
+func ifRangeMatched(ifRange, etag, lastModified string) bool {
+	if ifRange == "" {
+		return true
+	}
+	if strings.HasPrefix(ifRange, `"`) || strings.HasPrefix(ifRange, "W/") {
+		return !strings.HasPrefix(ifRange, "W/") && !strings.HasPrefix(etag, "W/") && ifRange == etag
+	}
+	return lastModified != "" && ifRange == lastModified
+}
+
+type limitReadCloser struct {
+	io.Reader
+	io.Closer
+}
+
+// 支持单个范围的Range请求，需在缓存中间件之前添加，缓存中保存的是完整的响应，
+// 以流方式返回的文件按文件大小(或范围大小)设置Content-Length
+func newRangeMiddleware() elton.Handler {
+	return func(c *elton.Context) error {
+		err := c.Next()
+		if err != nil {
+			return err
+		}
+		method := c.Request.Method
+		if method != http.MethodGet && method != http.MethodHead {
+			return nil
+		}
+		if c.StatusCode != 0 && c.StatusCode != http.StatusOK {
+			return nil
+		}
+		var size int64
+		var seeker io.ReadSeeker
+		if c.BodyBuffer != nil {
+			size = int64(c.BodyBuffer.Len())
+		} else if rs, ok := c.Body.(io.ReadSeeker); ok {
+			size, err = rs.Seek(0, io.SeekEnd)
+			if err != nil {
+				return err
+			}
+			_, err = rs.Seek(0, io.SeekStart)
+			if err != nil {
+				return err
+			}
+			seeker = rs
+			// 以流方式返回的文件也设置Content-Length，避免使用chunked
+			c.SetHeader("Content-Length", strconv.FormatInt(size, 10))
+		} else {
+			return nil
+		}
+		// 压缩后的数据不支持范围请求
+		if c.GetHeader("Content-Encoding") != "" {
+			return nil
+		}
+		c.SetHeader("Accept-Ranges", "bytes")
+		value := c.GetRequestHeader("Range")
+		if value == "" || method != http.MethodGet {
+			return nil
+		}
+		// If-Range不一致时返回完整的内容
+		if !ifRangeMatched(c.GetRequestHeader("If-Range"), c.GetHeader("ETag"), c.GetHeader("Last-Modified")) {
+			return nil
+		}
+		start, end, err := parseRange(value, size)
+		if err != nil {
+			if r, ok := c.Body.(io.Closer); ok {
+				_ = r.Close()
+			}
+			c.Body = nil
+			c.BodyBuffer = nil
+			c.Header().Del("Content-Length")
+			c.SetHeader("Content-Range", fmt.Sprintf("bytes */%d", size))
+			return err
+		}
+		c.SetHeader("Content-Range", fmt.Sprintf("bytes %d-%d/%d", start, end, size))
+		c.StatusCode = http.StatusPartialContent
+		if seeker == nil {
+			c.BodyBuffer = bytes.NewBuffer(c.BodyBuffer.Bytes()[start : end+1])
+			return nil
+		}
+		_, err = seeker.Seek(start, io.SeekStart)
+		if err != nil {
+			return err
+		}
+		r := &limitReadCloser{
+			Reader: io.LimitReader(seeker, end-start+1),
+		}
+		if closer, ok := seeker.(io.Closer); ok {
+			r.Closer = closer
+		} else {
+			r.Closer = io.NopCloser(nil)
+		}
+		c.SetHeader("Content-Length", strconv.FormatInt(end-start+1, 10))
+		c.Body = r
+		return nil
+	}
+}
@@ -0,0 +1,66 @@
+package main
+
+import (
+	"testing"
+)
+
+func TestParseRange(t *testing.T) {
+	tests := []struct {
+		value string
+		size  int64
+		start int64
+		end   int64
+		valid bool
+	}{
+		{"bytes=0-99", 1000, 0, 99, true},
+		{"bytes=500-", 1000, 500, 999, true},
+		{"bytes=900-2000", 1000, 900, 999, true},
+		{"bytes=-100", 1000, 900, 999, true},
+		{"bytes=-2000", 1000, 0, 999, true},
+		{"bytes= 10 - 20", 1000, 10, 20, true},
+		// 多个范围
+		{"bytes=0-99,200-299", 1000, 0, 0, false},
+		{"bytes=1000-", 1000, 0, 0, false},
+		{"bytes=100-99", 1000, 0, 0, false},
+		{"bytes=-0", 1000, 0, 0, false},
+		{"bytes=a-b", 1000, 0, 0, false},
+		{"bytes=0-99", 0, 0, 0, false},
+		{"items=0-99", 1000, 0, 0, false},
+	}
+	for _, tt := range tests {
+		start, end, err := parseRange(tt.value, tt.size)
+		if (err == nil) != tt.valid {
+			t.Errorf("range %s, valid: %v, error: %v", tt.value, tt.valid, err)
+			continue
+		}
+		if tt.valid && (start != tt.start || end != tt.end) {
+			t.Errorf("range %s, expected %d-%d, got %d-%d", tt.value, tt.start, tt.end, start, end)
+		}
+	}
+}
+
+func TestIfRangeMatched(t *testing.T) {
+	lastModified := "Wed, 21 Oct 2015 07:28:00 GMT"
+	tests := []struct {
+		ifRange string
+		etag    string
+		matched bool
+	}{
+		{"", `"abc"`, true},
+		// 强ETag一致
+		{`"abc"`, `"abc"`, true},
+		{`"abc"`, `"abd"`, false},
+		// 弱ETag均不匹配
+		{`W/"abc"`, `W/"abc"`, false},
+		{`W/"abc"`, `"abc"`, false},
+		{`"abc"`, `W/"abc"`, false},
+		{lastModified, `"abc"`, true},
+		{"Thu, 22 Oct 2015 07:28:00 GMT", `"abc"`, false},
+	}
+	for _, tt := range tests {
+		matched := ifRangeMatched(tt.ifRange, tt.etag, lastModified)
+		if matched != tt.matched {
+			t.Errorf("if range: %s, etag: %s, expected %v", tt.ifRange, tt.etag, tt.matched)
+		}
+	}
+}
@@ -0,0 +1,108 @@
+package main
+
+import (
+	"fmt"
+	"net/http"
+	"net/url"
+	"regexp"
+	"strings"
+
+	"github.com/vicanso/elton"
+)
+
+const (
+	redirectPrefix = "redirect:"
+	// 正则与替换路径的分隔符，空格不会出现在URL中
+	rewriteSeparator = " => "
+)
+
+type rewriteRule struct {
+	regexp      *regexp.Regexp
+	replacement string
+	// 替换路径中?之后的查询参数
+	query    string
+	redirect bool
+}
+
+// 解析重写规则，格式为pattern => replacement，多个以;分隔
+// replacement以redirect:开头时返回301重定向，否则为内部重写
+func parseRewriteRules(value string) ([]*rewriteRule, error) {
+	rules := make([]*rewriteRule, 0)
+	for _, item := range strings.Split(value, ";") {
+		item = strings.TrimSpace(item)
+		if item == "" {
+			continue
+		}
+		arr := strings.SplitN(item, rewriteSeparator, 2)
+		if len(arr) != 2 || strings.TrimSpace(arr[0]) == "" || strings.TrimSpace(arr[1]) == "" {
+			return nil, fmt.Errorf("rewrite rule is invalid, %s", item)
+		}
+		reg, err := regexp.Compile(strings.TrimSpace(arr[0]))
+		if err != nil {
+			return nil, err
+		}
+		replacement := strings.TrimSpace(arr[1])
+		rule := &rewriteRule{
+			regexp:      reg,
+			replacement: strings.TrimPrefix(replacement, redirectPrefix),
+			redirect:    strings.HasPrefix(replacement, redirectPrefix),
+		}
+		if index := strings.Index(rule.replacement, "?"); index != -1 {
+			rule.query = rule.replacement[index+1:]
+			rule.replacement = rule.replacement[:index]
+		}
+		rules = append(rules, rule)
+	}
+	return rules, nil
+}
+
+// 规范化后的规则，用于输出配置
+func (rule *rewriteRule) String() string {
+	target := rule.replacement
+	if rule.query != "" {
+		target += "?" + rule.query
+	}
+	if rule.redirect {
+		target = redirectPrefix + target
+	}
+	return rule.regexp.String() + rewriteSeparator + target
+}
+
+// 按规则重写路径，规则中的查询参数在前，请求的查询参数以&拼接在后
+func (rule *rewriteRule) apply(p, rawQuery string) (*url.URL, bool) {
+	match := rule.regexp.FindStringSubmatchIndex(p)
+	if match == nil {
+		return nil, false
+	}
+	query := string(rule.regexp.ExpandString(nil, rule.query, p, match))
+	if query != "" && rawQuery != "" {
+		query += "&"
+	}
+	return &url.URL{
+		Path:     rule.regexp.ReplaceAllString(p, rule.replacement),
+		RawQuery: query + rawQuery,
+	}, true
+}
+
+// 按规则重写请求路径，使用第一条匹配的规则
+func newRewriteMiddleware(rules []*rewriteRule) elton.Handler {
+	return func(c *elton.Context) error {
+		for _, rule := range rules {
+			target, ok := rule.apply(c.Request.URL.Path, c.Request.URL.RawQuery)
+			if !ok {
+				continue
+			}
+			if rule.redirect {
+				// 由url.URL生成，路径中的特殊字符会被转义
+				c.SetHeader("Location", target.String())
+				c.StatusCode = http.StatusMovedPermanently
+				return nil
+			}
+			c.Request.URL.Path = target.Path
+			c.Request.URL.RawPath = ""
+			c.Request.URL.RawQuery = target.RawQuery
+			break
+		}
+		return c.Next()
+	}
+}
@@ -0,0 +1,131 @@
+package main
+
+import (
+	"bytes"
+	"net/http"
+	"net/http/httptest"
+	"testing"
+
+	"github.com/vicanso/elton"
+)
+
+func TestParseRewriteRules(t *testing.T) {
+	rules, err := parseRewriteRules("^/old/(.*)$ => redirect:/new/$1; ^/app/.*$ => /index.html;^/v=(.*)$ => redirect:/new/$1?a=b")
+	if err != nil {
+		t.Fatal(err)
+	}
+	if len(rules) != 3 {
+		t.Fatalf("expected 3 rules, got %d", len(rules))
+	}
+	tests := []struct {
+		rule        *rewriteRule
+		replacement string
+		query       string
+		redirect    bool
+	}{
+		{rules[0], "/new/$1", "", true},
+		{rules[1], "/index.html", "", false},
+		// 正则与替换路径中均可包含=
+		{rules[2], "/new/$1", "a=b", true},
+	}
+	for _, tt := range tests {
+		if tt.rule.redirect != tt.redirect {
+			t.Errorf("rule %s, expected redirect: %v", tt.rule.regexp, tt.redirect)
+		}
+		if tt.rule.replacement != tt.replacement || tt.rule.query != tt.query {
+			t.Errorf("rule %s, expected %s?%s, got %s?%s", tt.rule.regexp, tt.replacement, tt.query, tt.rule.replacement, tt.rule.query)
+		}
+	}
+	if value := rules[2].String(); value != "^/v=(.*)$ => redirect:/new/$1?a=b" {
+		t.Errorf("unexpected normalized rule %s", value)
+	}
+
+	rules, err = parseRewriteRules("")
+	if err != nil || len(rules) != 0 {
+		t.Errorf("empty value should return no rule, %v", err)
+	}
+
+	for _, value := range []string{"^/old/.*$", "^/old/.*$=/index.html", " => /index.html", "^/old/.*$ => ", "^/(old => /index.html"} {
+		_, err = parseRewriteRules(value)
+		if err == nil {
+			t.Errorf("rule %s should be invalid", value)
+		}
+	}
+}
+
+func TestRewriteRuleApply(t *testing.T) {
+	rules, err := parseRewriteRules("^/legacy/(.*)$ => redirect:/new/$1?from=legacy;^/old/(.*)$ => redirect:/new/$1;^/app/.*$ => /index.html;^/u/(\\w+)$ => /user.html?name=$1")
+	if err != nil {
+		t.Fatal(err)
+	}
+	tests := []struct {
+		path     string
+		rawQuery string
+		location string
+		matched  bool
+	}{
+		// 规则与请求均有查询参数时以&拼接
+		{"/legacy/a", "x=1", "/new/a?from=legacy&x=1", true},
+		{"/legacy/a", "", "/new/a?from=legacy", true},
+		{"/old/a", "x=1&y=2", "/new/a?x=1&y=2", true},
+		{"/old/a", "", "/new/a", true},
+		// 路径中的特殊字符需转义
+		{"/old/a b?c", "", "/new/a%20b%3Fc", true},
+		{"/app/users/1", "tab=2", "/index.html?tab=2", true},
+		// 查询参数中的分组同样替换
+		{"/u/tree", "", "/user.html?name=tree", true},
+		{"/other/a", "x=1", "", false},
+	}
+	for _, tt := range tests {
+		var target string
+		matched := false
+		for _, rule := range rules {
+			u, ok := rule.apply(tt.path, tt.rawQuery)
+			if ok {
+				target = u.String()
+				matched = true
+				break
+			}
+		}
+		if matched != tt.matched || target != tt.location {
+			t.Errorf("rewrite %s?%s, expected %s, got %s", tt.path, tt.rawQuery, tt.location, target)
+		}
+	}
+}
+
+func TestRewriteMiddleware(t *testing.T) {
+	rules, err := parseRewriteRules("^/legacy/(.*)$ => redirect:/new/$1?from=legacy;^/app/.*$ => /index.html")
+	if err != nil {
+		t.Fatal(err)
+	}
+	e := elton.New()
+	e.Use(newRewriteMiddleware(rules))
+	e.GET("/*", func(c *elton.Context) error {
+		c.BodyBuffer = bytes.NewBufferString(c.Request.URL.Path + "?" + c.Request.URL.RawQuery)
+		return nil
+	})
+	tests := []struct {
+		uri      string
+		status   int
+		location string
+		body     string
+	}{
+		{"/legacy/a?x=1", http.StatusMovedPermanently, "/new/a?from=legacy&x=1", ""},
+		{"/legacy/a%20b", http.StatusMovedPermanently, "/new/a%20b?from=legacy", ""},
+		{"/app/users?tab=2", http.StatusOK, "", "/index.html?tab=2"},
+		{"/other.js?v=1", http.StatusOK, "", "/other.js?v=1"},
+	}
+	for _, tt := range tests {
+		resp := httptest.NewRecorder()
+		e.ServeHTTP(resp, httptest.NewRequest(http.MethodGet, tt.uri, nil))
+		if resp.Code != tt.status {
+			t.Errorf("%s, expected status %d, got %d", tt.uri, tt.status, resp.Code)
+		}
+		if location := resp.Header().Get("Location"); location != tt.location {
+			t.Errorf("%s, expected location %s, got %s", tt.uri, tt.location, location)
+		}
+		if tt.body != "" && resp.Body.String() != tt.body {
+			t.Errorf("%s, expected body %s, got %s", tt.uri, tt.body, resp.Body.String())
+		}
+	}
+}
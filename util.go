@@ -0,0 +1,116 @@
+package main
+
+import (
+	"bytes"
+	"encoding/json"
+	"fmt"
+	"net"
+	"os"
+	"path/filepath"
+	"strings"
+)
+
+func splitAndTrim(value string) []string {
+	arr := make([]string, 0)
+	for _, item := range strings.Split(value, ",") {
+		item = strings.TrimSpace(item)
+		if item != "" {
+			arr = append(arr, item)
+		}
+	}
+	return arr
+}
+
+// 解析ext=value格式的配置，多个配置以sep分隔，扩展名统一转为小写
+func parseExtValues(value, sep string) map[string]string {
+	m := make(map[string]string)
+	for _, item := range strings.Split(value, sep) {
+		arr := strings.SplitN(item, "=", 2)
+		if len(arr) != 2 {
+			continue
+		}
+		ext := strings.ToLower(strings.TrimPrefix(strings.TrimSpace(arr[0]), "."))
+		v := strings.TrimSpace(arr[1])
+		if ext != "" && v != "" {
+			m[ext] = v
+		}
+	}
+	return m
+}
+
+// 解析以,分隔的扩展名，去除前缀.并转为小写
+func parseExts(value string) []string {
+	exts := splitAndTrim(value)
+	for i, ext := range exts {
+		exts[i] = strings.ToLower(strings.TrimPrefix(ext, "."))
+	}
+	return exts
+}
+
+// 加载json配置文件，格式为{"环境变量名": 值}，已设置的环境变量不覆盖
+func loadConfigFile(file string) error {
+	buf, err := os.ReadFile(file)
+	if err != nil {
+		return err
+	}
+	values := make(map[string]interface{})
+	decoder := json.NewDecoder(bytes.NewReader(buf))
+	// 数字保持原样，避免转换为浮点数
+	decoder.UseNumber()
+	err = decoder.Decode(&values)
+	if err != nil {
+		return err
+	}
+	for key, value := range values {
+		if _, exists := os.LookupEnv(key); exists {
+			continue
+		}
+		switch value.(type) {
+		case string, json.Number, bool:
+		default:
+			return fmt.Errorf("value of %s should be string, number or bool", key)
+		}
+		err = os.Setenv(key, fmt.Sprint(value))
+		if err != nil {
+			return err
+		}
+	}
+	return nil
+}
+
+// 按顺序查找首页文件，返回第一个存在的文件
+func findIndexFile(dir string, indexFiles []string) (string, os.FileInfo) {
+	for _, name := range indexFiles {
+		file := filepath.Join(dir, name)
+		info, err := os.Stat(file)
+		if err == nil && !info.IsDir() {
+			return file, info
+		}
+	}
+	return "", nil
+}
+
+// 检查静态文件目录与首页文件是否可访问
+func checkStorage(staticPath string, indexFiles []string) error {
+	_, err := os.Stat(staticPath)
+	if err != nil {
+		return fmt.Errorf("static storage is unreachable, %w", err)
+	}
+	if file, _ := findIndexFile(staticPath, indexFiles); file == "" {
+		return fmt.Errorf("index file is not found, %s", strings.Join(indexFiles, ","))
+	}
+	return nil
+}
+
+// 监听地址，unix:开头的为unix socket
+func listen(addr string) (net.Listener, error) {
+	if !strings.HasPrefix(addr, "unix:") {
+		return net.Listen("tcp", addr)
+	}
+	file := strings.TrimPrefix(addr, "unix:")
+	// 删除上次运行遗留的socket文件
+	if info, err := os.Lstat(file); err == nil && info.Mode()&os.ModeSocket != 0 {
+		_ = os.Remove(file)
+	}
+	return net.Listen("unix", file)
+}
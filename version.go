@@ -0,0 +1,30 @@
+package main
+
+import (
+	"bytes"
+	"encoding/json"
+
+	"github.com/vicanso/elton"
+)
+
+// 编译时通过ldflags设置
+var (
+	Version   = "dev"
+	CommitID  = ""
+	BuildedAt = ""
+)
+
+func versionHandler(c *elton.Context) error {
+	buf, err := json.Marshal(map[string]string{
+		"version":   Version,
+		"commitID":  CommitID,
+		"buildedAt": BuildedAt,
+	})
+	if err != nil {
+		return err
+	}
+	c.NoCache()
+	c.SetContentTypeByExt(".json")
+	c.BodyBuffer = bytes.NewBuffer(buf)
+	return nil
+}